@@ -0,0 +1,87 @@
+//! Implementation of a SocketCAN reader for Linux.
+//! Never closes, i.e., will try to read indefinitely.
+use crate::nmea::nmea2000::socketcan::Frame;
+use crate::nmea::timestamp_from_secs;
+
+use std::ffi::CString;
+use std::io;
+use std::mem;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+use std::time::SystemTime;
+
+/// Raw CAN socket bound to a single interface, e.g., `can0` or `vcan0`.
+/// 
+/// Only extended data frames are returned by [`CanSocket::read_frame`], standard,
+/// remote and error frames are skipped since they do not carry NMEA 2000 messages.
+pub struct CanSocket{
+    fd: OwnedFd
+}
+
+impl CanSocket{
+    /// Opens a raw CAN socket and binds it to interface `ifname`.
+    pub fn open(ifname: &str) -> io::Result<Self>{
+        let name = CString::new(ifname)?;
+        let ifindex = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if ifindex == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let fd = unsafe { libc::socket(libc::PF_CAN, libc::SOCK_RAW, libc::CAN_RAW) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let mut addr: libc::sockaddr_can = unsafe { mem::zeroed() };
+        addr.can_family = libc::AF_CAN as libc::sa_family_t;
+        addr.can_ifindex = ifindex as libc::c_int;
+        let r = unsafe {
+            libc::bind(fd.as_raw_fd(),
+                       &addr as *const libc::sockaddr_can as *const libc::sockaddr,
+                       mem::size_of::<libc::sockaddr_can>() as libc::socklen_t)
+        };
+        if r < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(CanSocket{fd})
+    }
+
+    /// Blocks until the next extended data frame is received.
+    pub fn read_frame(&mut self) -> io::Result<Frame>{
+        loop{
+            let mut frame: libc::can_frame = unsafe { mem::zeroed() };
+            let n = unsafe {
+                libc::read(self.fd.as_raw_fd(),
+                           &mut frame as *mut libc::can_frame as *mut libc::c_void,
+                           mem::size_of::<libc::can_frame>())
+            };
+            if n < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if n as usize != mem::size_of::<libc::can_frame>() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "incomplete CAN frame"));
+            }
+            if (frame.can_id & libc::CAN_EFF_FLAG == 0)
+                || (frame.can_id & (libc::CAN_RTR_FLAG | libc::CAN_ERR_FLAG) != 0){
+                continue;
+            }
+
+            let t = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap();
+            return Ok(Frame{
+                timestamp: timestamp_from_secs(t.as_secs_f64()),
+                id: frame.can_id & libc::CAN_EFF_MASK,
+                len: frame.can_dlc.min(8),
+                data: frame.data
+            });
+        }
+    }
+}
+
+impl Iterator for CanSocket{
+    type Item = io::Result<Frame>;
+
+    /// Reads the next frame. Never returns `None`.
+    fn next(&mut self) -> Option<Self::Item>{
+        Some(self.read_frame())
+    }
+}
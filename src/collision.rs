@@ -0,0 +1,179 @@
+//! Collision risk alarms for AIS targets based on the closest point of approach (CPA).
+//!
+//! CPA and the time to CPA (TCPA) are computed on a flat earth around the own position with the
+//! current COG and SOG of both vessels, which is sufficiently accurate within the AIS range.
+use crate::nmea::types::Timestamp;
+use crate::vessels::Vessel;
+
+use std::collections::BTreeSet;
+
+/// Headline of the CSV document of the alarm events
+pub const HEADLINE: &str = "time;mmsi;name;event;cpa;tcpa";
+
+/// Alarm raised or cleared for a target
+pub struct Event{
+    /// Timestamp of the position report that raised or cleared the alarm
+    pub timestamp : Timestamp,
+    /// MMSI of the target
+    pub mmsi : u32,
+    /// Name of the target, empty if not known
+    pub name : String,
+    /// `true` if the alarm is raised, `false` if it is cleared
+    pub alarm : bool,
+    /// Closest point of approach in nautical miles
+    pub cpa : f32,
+    /// Time to the closest point of approach in minutes
+    pub tcpa : f32,
+}
+
+/// Keeps track of the targets for which an alarm is raised.
+pub struct Alarms{
+    /// Alarm distance in nautical miles
+    cpa : f32,
+    /// Alarm time in minutes
+    tcpa : f32,
+    /// Targets with a raised alarm
+    active : BTreeSet<u32>,
+    /// Events since the last call of [`Alarms::take_events`]
+    events : Vec<Event>,
+}
+
+/// Returns the velocity in knots east and north. A vessel with SOG 0 or not available doesn't move,
+/// e.g., at anchor, even if the COG is not available.
+fn velocity(cog: f32, sog: f32) -> (f32, f32){
+    if sog.is_nan() || sog == 0.0{
+        return (0.0, 0.0);
+    }
+    (sog * cog.to_radians().sin(), sog * cog.to_radians().cos())
+}
+
+/// Returns CPA in nautical miles and TCPA in minutes of `target` and the own vessel at `latitude`,
+/// `longitude` with `cog` in degrees and `sog` in knots. TCPA is negative if the vessels move apart.
+pub fn cpa_tcpa(latitude: f32, longitude: f32, cog: f32, sog: f32, target: &Vessel) -> (f32, f32){
+    //Position of the target relative to the own vessel in nautical miles east and north
+    let x = (target.longitude - longitude) * 60.0 * latitude.to_radians().cos();
+    let y = (target.latitude - latitude) * 60.0;
+    //Relative velocity in knots
+    let (tx, ty) = velocity(target.cog, target.sog);
+    let (ox, oy) = velocity(cog, sog);
+    let (vx, vy) = (tx - ox, ty - oy);
+
+    let v2 = vx * vx + vy * vy;
+    //Time to CPA in hours, the distance does not change without relative motion
+    let t = if v2 > 0.0 { -(x * vx + y * vy) / v2 } else { 0.0 };
+    let (cx, cy) = (x + vx * t, y + vy * t);
+    ((cx * cx + cy * cy).sqrt(), t * 60.0)
+}
+
+impl Alarms{
+    /// Create alarms for targets that come closer than `cpa` nautical miles within `tcpa` minutes
+    pub fn new(cpa: f32, tcpa: f32) -> Alarms{
+        Alarms{cpa, tcpa, active: BTreeSet::new(), events: Vec::new()}
+    }
+
+    /// Raises or clears the alarm of `target` for the own vessel at `latitude`, `longitude`
+    /// with `cog` in degrees and `sog` in knots
+    pub fn check(&mut self, latitude: f32, longitude: f32, cog: f32, sog: f32, target: &Vessel){
+        let (cpa, tcpa) = cpa_tcpa(latitude, longitude, cog, sog, target);
+        //COG of a moving vessel not available, the alarm stays as it is
+        if cpa.is_nan() || tcpa.is_nan(){
+            return;
+        }
+        let danger = cpa <= self.cpa && (0.0..=self.tcpa).contains(&tcpa);
+        let changed = if danger{
+            self.active.insert(target.mmsi)
+        }else{
+            self.active.remove(&target.mmsi)
+        };
+        if changed{
+            self.events.push(Event{timestamp: target.last_seen, mmsi: target.mmsi, name: target.name.clone(),
+                                   alarm: danger, cpa, tcpa});
+        }
+    }
+
+    /// Returns the events since the last call
+    pub fn take_events(&mut self) -> Vec<Event>{
+        std::mem::take(&mut self.events)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    /// Target `north` nautical miles north of 50°N 0°E
+    fn target(north: f32, cog: f32, sog: f32) -> Vessel{
+        Vessel{mmsi: 211000001, latitude: 50.0 + north / 60.0, longitude: 0.0, cog, sog, ..Default::default()}
+    }
+
+    #[test]
+    fn head_on(){
+        let (cpa, tcpa) = cpa_tcpa(50.0, 0.0, 0.0, 10.0, &target(1.0, 180.0, 10.0));
+        assert!(cpa < 0.01);
+        assert!((tcpa - 3.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn diverging(){
+        let (_, tcpa) = cpa_tcpa(50.0, 0.0, 0.0, 5.0, &target(1.0, 0.0, 10.0));
+        assert!(tcpa < 0.0);
+    }
+
+    #[test]
+    fn passing_abeam(){
+        //Target 1 NM east on a parallel course
+        let mut t = target(0.0, 180.0, 10.0);
+        t.longitude = 1.0 / 60.0 / 50f32.to_radians().cos();
+        let (cpa, _) = cpa_tcpa(50.0, 0.0, 0.0, 10.0, &t);
+        assert!((cpa - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn alarm_raised_and_cleared(){
+        let mut alarms = Alarms::new(0.5, 15.0);
+        alarms.check(50.0, 0.0, 0.0, 10.0, &target(1.0, 180.0, 10.0));
+        alarms.check(50.0, 0.0, 0.0, 10.0, &target(0.9, 180.0, 10.0));
+        let events = alarms.take_events();
+        assert_eq!(events.len(), 1);
+        assert!(events[0].alarm);
+        //Target turned away
+        alarms.check(50.0, 0.0, 0.0, 10.0, &target(0.8, 0.0, 20.0));
+        let events = alarms.take_events();
+        assert_eq!(events.len(), 1);
+        assert!(!events[0].alarm);
+        assert!(alarms.take_events().is_empty());
+    }
+
+    #[test]
+    fn no_alarm_beyond_tcpa(){
+        let mut alarms = Alarms::new(0.5, 15.0);
+        //Head on but 30 minutes away
+        alarms.check(50.0, 0.0, 0.0, 5.0, &target(5.0, 180.0, 5.0));
+        assert!(alarms.take_events().is_empty());
+    }
+
+    #[test]
+    fn stationary_target(){
+        //At anchor, COG not available
+        let (cpa, tcpa) = cpa_tcpa(50.0, 0.0, 0.0, 10.0, &target(1.0, f32::NAN, 0.0));
+        assert!(cpa < 0.01);
+        assert!((tcpa - 6.0).abs() < 0.01);
+        let mut alarms = Alarms::new(0.5, 15.0);
+        alarms.check(50.0, 0.0, 0.0, 10.0, &target(1.0, f32::NAN, f32::NAN));
+        assert_eq!(alarms.take_events().len(), 1);
+    }
+
+    #[test]
+    fn stationary_own_vessel(){
+        let mut alarms = Alarms::new(0.5, 15.0);
+        alarms.check(50.0, 0.0, f32::NAN, 0.0, &target(1.0, 180.0, 10.0));
+        assert_eq!(alarms.take_events().len(), 1);
+    }
+
+    #[test]
+    fn moving_target_without_cog(){
+        let mut alarms = Alarms::new(0.5, 15.0);
+        alarms.check(50.0, 0.0, 0.0, 10.0, &target(1.0, f32::NAN, 5.0));
+        assert!(alarms.take_events().is_empty());
+    }
+}
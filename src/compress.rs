@@ -0,0 +1,134 @@
+//! Transparent gzip and zstd compression of input and output files.
+//!
+//! The compression is chosen by the file extension, `.gz` or `.zst`. Both are implemented
+//! in pure Rust so that cross compiling does not need a C toolchain.
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use ruzstd::decoding::{FrameDecoder, StreamingDecoder};
+use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+
+/// Size of the uncompressed data of a single zstd frame written by [`ZstdWriter`]
+const ZSTD_FRAME_SIZE: usize = 1 << 20;
+
+/// Compression of a file
+enum Compression{
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression{
+    fn from_path(path: &Path) -> Self{
+        match path.extension().and_then(|e| e.to_str()){
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+}
+
+/// Returns a reader that decompresses `file` according to the extension of `path`.
+pub fn reader<R: Read+Send+'static>(path: &Path, file: R) -> Box<dyn Read+Send>{
+    match Compression::from_path(path){
+        Compression::None => Box::new(file),
+        Compression::Gzip => Box::new(MultiGzDecoder::new(BufReader::new(file))),
+        Compression::Zstd => Box::new(ZstdReader::new(BufReader::new(file))),
+    }
+}
+
+/// Returns a writer that compresses into `file` according to the extension of `path`.
+///
+/// The compressed stream is completed when the writer is dropped.
+pub fn writer<W: Write+Send+'static>(path: &Path, file: W) -> Box<dyn Write+Send>{
+    match Compression::from_path(path){
+        Compression::None => Box::new(file),
+        Compression::Gzip => Box::new(GzEncoder::new(file, flate2::Compression::default())),
+        Compression::Zstd => Box::new(ZstdWriter::new(file)),
+    }
+}
+
+/// Decompresses a zstd stream that consists of one or more frames.
+struct ZstdReader<R: BufRead>{
+    source: Option<R>,
+    decoder: Option<StreamingDecoder<R, FrameDecoder>>,
+}
+
+impl<R: BufRead> ZstdReader<R>{
+    fn new(source: R) -> Self{
+        ZstdReader{source: Some(source), decoder: None}
+    }
+}
+
+impl<R: BufRead> Read for ZstdReader<R>{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize>{
+        loop{
+            if let Some(decoder) = &mut self.decoder{
+                let n = decoder.read(buf)?;
+                if n > 0 || buf.is_empty(){
+                    return Ok(n);
+                }
+                //Frame finished, continue with the next one
+                self.source = self.decoder.take().map(|d| d.into_inner());
+            }
+            let mut source = match self.source.take(){
+                Some(s) => s,
+                None => return Ok(0),
+            };
+            if source.fill_buf()?.is_empty(){
+                return Ok(0);
+            }
+            self.decoder = Some(StreamingDecoder::new(source)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?);
+        }
+    }
+}
+
+/// Compresses into a zstd stream.
+///
+/// The data is compressed in independent frames of [`ZSTD_FRAME_SIZE`] bytes which bounds the
+/// memory usage. A frame is written when the buffer is full and when the writer is dropped,
+/// flushing only flushes the underlying writer.
+struct ZstdWriter<W: Write>{
+    inner: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> ZstdWriter<W>{
+    fn new(inner: W) -> Self{
+        ZstdWriter{inner, buffer: Vec::with_capacity(ZSTD_FRAME_SIZE)}
+    }
+
+    /// Compresses the buffer into a frame
+    fn write_frame(&mut self) -> std::io::Result<()>{
+        if !self.buffer.is_empty(){
+            let frame = compress_to_vec(&self.buffer[..], CompressionLevel::Fastest);
+            self.inner.write_all(&frame)?;
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ZstdWriter<W>{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize>{
+        let n = buf.len().min(ZSTD_FRAME_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..n]);
+        if self.buffer.len() == ZSTD_FRAME_SIZE{
+            self.write_frame()?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()>{
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for ZstdWriter<W>{
+    fn drop(&mut self){
+        let _ = self.write_frame().and_then(|_| self.inner.flush());
+    }
+}
@@ -0,0 +1,436 @@
+//! Input sources and their raw formats.
+use crate::udpstream::UdpStream;
+use crate::tcpstream::TcpLines;
+use crate::replay::{Broadcast, Replay};
+use crate::compress;
+use crate::pcap::{Packet, UdpPayloads};
+#[cfg(target_os = "linux")]
+use crate::canstream::CanSocket;
+use crate::nmea::{nmea0183, nmea2000, MessageValue, Parse};
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+
+/// Message values parsed from an input
+pub type Values = Box<dyn Iterator<Item=Result<Vec<MessageValue>>> + Send>;
+
+/// Number of lines that are inspected to detect the format of an input
+const DETECT_LINES: usize = 20;
+
+/// Raw format of the input
+#[derive(Debug, Clone, Copy)]
+pub enum Format{
+    /// Detected from the first lines, see [`Input::detect`]
+    Auto,
+    /// Yacht Devices RAW
+    Yd,
+    /// Actisense NGT-1 binary
+    Actisense,
+    /// Linux candump log
+    Candump,
+    /// canboat plain format
+    Canboat,
+    /// SeaSmart $PCDIN sentences
+    Pcdin,
+    /// Actisense N2K ASCII
+    N2kAscii,
+    /// Yacht Devices Voyage Recorder binary recording
+    Ydvr,
+    /// NMEA 0183 sentences
+    Nmea0183,
+}
+
+impl Format{
+    pub const VARIANTS: [&'static str; 9] = 
+        ["auto", "yd", "actisense", "candump", "canboat", "pcdin", "n2kascii", "ydvr", "nmea0183"];
+}
+
+impl FromStr for Format{
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self>{
+        match s{
+            "auto" => Ok(Format::Auto),
+            "yd" => Ok(Format::Yd),
+            "actisense" => Ok(Format::Actisense),
+            "candump" => Ok(Format::Candump),
+            "canboat" => Ok(Format::Canboat),
+            "pcdin" => Ok(Format::Pcdin),
+            "n2kascii" => Ok(Format::N2kAscii),
+            "ydvr" => Ok(Format::Ydvr),
+            "nmea0183" => Ok(Format::Nmea0183),
+            _ => anyhow::bail!("unknown format {}", s),
+        }
+    }
+}
+
+/// Source of the input
+pub enum Input{
+    /// Byte stream, e.g., a file or UDP packets
+    Stream(Box<dyn std::io::Read+Send>),
+    /// Complete lines, e.g., from a TCP connection
+    Lines(Box<dyn Iterator<Item=std::io::Result<String>>+Send>),
+    /// SocketCAN interface
+    #[cfg(target_os = "linux")]
+    Can(CanSocket),
+}
+
+impl Input{
+    /// Opens file `path`, decompressing `.gz` and `.zst` files
+    pub fn file(path: &Path) -> Result<Self>{
+        Ok(Input::Stream(compress::reader(path,
+            File::open(path).with_context(|| format!("unable to open {}", path.display()))?
+        )))
+    }
+
+    /// Reads the UDP packets to `port` from the pcap or pcapng capture `path` as Yacht Devices
+    /// RAW lines. The time of every line is replaced by the capture date and time of its packet.
+    pub fn pcap(path: &Path, port: u16) -> Result<Self>{
+        let file = File::open(path).with_context(|| format!("unable to open {}", path.display()))?;
+        let packets = UdpPayloads::new(compress::reader(path, file), port);
+        Ok(Input::Lines(Box::new(packets.flat_map(capture_lines))))
+    }
+
+    /// Listens for UDP packets on `port`
+    pub fn udp(port: u16) -> Result<Self>{
+        let address = format!("0.0.0.0:{}",port);
+        Ok(Input::Stream(Box::new(
+            UdpStream::open(address.clone())
+                .with_context(|| format!("could not open UDP listener on {}",address))?
+        )))
+    }
+
+    /// Connects to TCP server `address`
+    pub fn tcp(address: String) -> Self{
+        Input::Lines(Box::new(TcpLines::new(address)))
+    }
+
+    /// Reads from SocketCAN `interface`
+    #[cfg(target_os = "linux")]
+    pub fn can(interface: &str) -> Result<Self>{
+        Ok(Input::Can(
+            CanSocket::open(interface)
+                .with_context(|| format!("could not open CAN interface {}",interface))?
+        ))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn can(interface: &str) -> Result<Self>{
+        anyhow::bail!("SocketCAN is not supported on this platform, could not open {}", interface)
+    }
+
+    /// Returns the input as lines for line based formats
+    pub fn lines(self) -> Result<Box<dyn Iterator<Item=std::io::Result<String>>+Send>>{
+        match self{
+            Input::Stream(s) => Ok(Box::new(BufReader::new(s).lines())),
+            Input::Lines(l) => Ok(l),
+            #[cfg(target_os = "linux")]
+            Input::Can(_) => anyhow::bail!("CAN interfaces have no line based format"),
+        }
+    }
+
+    /// Returns the input as byte stream for binary formats
+    pub fn stream(self) -> Result<Box<dyn std::io::Read+Send>>{
+        match self{
+            Input::Stream(s) => Ok(s),
+            Input::Lines(_) => anyhow::bail!("binary formats are not supported over TCP"),
+            #[cfg(target_os = "linux")]
+            Input::Can(_) => anyhow::bail!("CAN interfaces have no binary format"),
+        }
+    }
+
+    /// Detects the raw format of the input if `format` is [`Format::Auto`] and returns the input
+    /// together with its format.
+    /// 
+    /// The first lines are inspected until one of them is a valid line of a line based format. 
+    /// Byte streams that contain the DLE STX sequence are taken as Actisense NGT-1 binary, byte
+    /// streams that start with a service record as YDVR recording. 
+    /// SocketCAN interfaces need no format.
+    pub fn detect(self, format: Format) -> Result<(Self, Format)>{
+        if !matches!(format, Format::Auto){
+            return Ok((self, format));
+        }
+        match self{
+            Input::Stream(s) => {
+                let mut reader = BufReader::new(s);
+                let buffer = reader.fill_buf().context("error reading input")?;
+                if buffer.windows(2).any(|w| w == [nmea2000::actisense::DLE, nmea2000::actisense::STX]){
+                    return Ok((Input::Stream(Box::new(reader)), Format::Actisense));
+                }
+                if buffer.starts_with(&nmea2000::ydvr::SERVICE_RECORD.to_le_bytes()){
+                    return Ok((Input::Stream(Box::new(reader)), Format::Ydvr));
+                }
+                Input::Lines(Box::new(reader.lines())).detect(format)
+            }
+            Input::Lines(mut lines) => {
+                let mut inspected = Vec::new();
+                while inspected.len() < DETECT_LINES{
+                    let line = match lines.next(){
+                        Some(line) => line.context("error reading input")?,
+                        None => break,
+                    };
+                    let detected = detect_line(&line);
+                    inspected.push(line);
+                    if let Some(format) = detected{
+                        return Ok((Input::Lines(Box::new(inspected.into_iter().map(Ok).chain(lines))), format));
+                    }
+                }
+                anyhow::bail!("unable to detect the format of the input, select it with --format")
+            }
+            #[cfg(target_os = "linux")]
+            Input::Can(socket) => Ok((Input::Can(socket), format)),
+        }
+    }
+
+    /// Paces a recorded input in `format` at `speed` times the recorded speed, see [`Replay`].
+    pub fn replay(self, format: Format, speed: f64) -> Result<Self>{
+        Ok(Input::Lines(match format{
+            Format::Yd => 
+                Box::new(Replay::<nmea2000::yd::Raw,_,_>::new(self.lines()?, speed)),
+            Format::Candump => 
+                Box::new(Replay::<nmea2000::candump::Raw,_,_>::new(self.lines()?, speed)),
+            Format::Canboat => 
+                Box::new(Replay::<nmea2000::canboat::Raw,_,_>::new(self.lines()?, speed)),
+            Format::Pcdin => 
+                Box::new(Replay::<nmea2000::pcdin::Raw,_,_>::new(self.lines()?, speed)),
+            Format::N2kAscii => 
+                Box::new(Replay::<nmea2000::n2kascii::Raw,_,_>::new(self.lines()?, speed)),
+            _ => anyhow::bail!("replay is not supported for format {:?}, no recorded timestamps", format),
+        }))
+    }
+
+    /// Sends every line of the input to UDP `address`, see [`Broadcast`].
+    pub fn broadcast(self, address: &str) -> Result<Self>{
+        Ok(Input::Lines(Box::new(
+            Broadcast::new(self.lines()?, address)
+                .with_context(|| format!("could not broadcast to {}", address))?
+        )))
+    }
+
+    /// Returns the message values of the input in `format`
+    pub fn values(self, format: Format) -> Result<Values>{
+        Ok(match (self, format){
+            #[cfg(target_os = "linux")]
+            (Input::Can(socket), _) =>
+                parse(socket, nmea2000::Parser::<nmea2000::socketcan::Raw,_>::new()),
+            (input, Format::Yd) =>
+                parse(input.lines()?, nmea2000::Parser::<nmea2000::yd::Raw,_>::new()),
+            (input, Format::Actisense) =>
+                parse(nmea2000::actisense::Reader::new(input.stream()?),
+                      nmea2000::Parser::<nmea2000::actisense::Raw,_>::new()),
+            (input, Format::Candump) =>
+                parse(input.lines()?, nmea2000::Parser::<nmea2000::candump::Raw,_>::new()),
+            (input, Format::Canboat) =>
+                parse(input.lines()?, nmea2000::Parser::<nmea2000::canboat::Raw,_>::new()),
+            (input, Format::Pcdin) =>
+                parse(input.lines()?, nmea2000::Parser::<nmea2000::pcdin::Raw,_>::new()),
+            (input, Format::N2kAscii) =>
+                parse(input.lines()?, nmea2000::Parser::<nmea2000::n2kascii::Raw,_>::new()),
+            (input, Format::Ydvr) =>
+                parse(nmea2000::ydvr::Reader::new(input.stream()?),
+                      nmea2000::Parser::<nmea2000::socketcan::Raw,_>::new()),
+            (input, Format::Nmea0183) =>
+                parse(input.lines()?, nmea0183::Parser::new()),
+            (_, Format::Auto) =>
+                anyhow::bail!("the format of the input has to be detected first"),
+        })
+    }
+}
+
+/// Parses every item of `input` with `parser`
+fn parse<P,U,I>(input: I, mut parser: P) -> Values
+    where
+        I: Iterator<Item=std::io::Result<U>> + Send + 'static,
+        P: Parse<U> + Send + 'static,
+    {
+        Box::new(input.filter_map(move |item| {
+            item.context("error reading input")
+                .and_then(|item| parser.parse_values(&item).context("error parsing input"))
+                .transpose()
+        }))
+}
+
+/// Returns the format of `line` if it is a valid line of one of the line based formats
+fn detect_line(line: &str) -> Option<Format>{
+    fn parses<T: nmea2000::Raw + nmea2000::From<String>>(line: &String) -> bool{
+        T::from(line).is_ok()
+    }
+
+    let line = line.trim().to_string();
+    if parses::<nmea2000::pcdin::Raw>(&line){
+        Some(Format::Pcdin)
+    } else if (line.starts_with('$') || line.starts_with('!')) && line.contains(','){
+        Some(Format::Nmea0183)
+    } else if parses::<nmea2000::yd::Raw>(&line){
+        Some(Format::Yd)
+    } else if parses::<nmea2000::candump::Raw>(&line){
+        Some(Format::Candump)
+    } else if parses::<nmea2000::canboat::Raw>(&line){
+        Some(Format::Canboat)
+    } else if parses::<nmea2000::n2kascii::Raw>(&line){
+        Some(Format::N2kAscii)
+    } else {
+        None
+    }
+}
+
+/// Splits a captured packet into Yacht Devices RAW lines with the capture date and time
+fn capture_lines(packet: std::io::Result<Packet>) -> Vec<std::io::Result<String>>{
+    match packet{
+        Ok(packet) => {
+            String::from_utf8_lossy(&packet.payload).lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(|line| Ok(nmea2000::yd::with_capture_time(line, packet.timestamp)))
+                .collect()
+        }
+        Err(e) => vec![Err(e)],
+    }
+}
+
+/// Returns true if `path` is a character device, e.g., a serial port.
+#[cfg(unix)]
+pub fn is_device(path: &Path) -> bool{
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).map(|m| m.file_type().is_char_device()).unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+pub fn is_device(_path: &Path) -> bool{
+    false
+}
+
+/// Kind of an input source
+#[derive(Debug)]
+pub enum Kind{
+    File,
+    Udp,
+    Tcp,
+    Can,
+    /// Packet capture, the target is `PORT:PATH`
+    Pcap,
+}
+
+/// Specification of an input source in the form `[LABEL=]KIND[+FORMAT]:TARGET`, e.g.,
+/// `gw1=udp:1457`, `tcp:192.168.4.1:1456`, `can:can0`, `mux=file+nmea0183:/dev/ttyUSB0` or
+/// `pcap:1457:capture.pcapng`.
+///
+/// The label defaults to `KIND:TARGET`, the format is detected if it is not given.
+#[derive(Debug)]
+pub struct Spec{
+    pub label: String,
+    pub kind: Kind,
+    pub format: Format,
+    pub target: String,
+}
+
+impl Spec{
+    /// Returns true if the source is read like a network stream, i.e., is not a regular file
+    pub fn is_live(&self) -> bool{
+        match self.kind{
+            Kind::File => is_device(Path::new(&self.target)),
+            Kind::Pcap => false,
+            _ => true
+        }
+    }
+
+    /// Opens the input source
+    pub fn open(&self) -> Result<Input>{
+        match self.kind{
+            Kind::File => Input::file(Path::new(&self.target)),
+            Kind::Udp => Input::udp(u16::from_str(&self.target)
+                            .with_context(|| format!("invalid port {}", self.target))?),
+            Kind::Tcp => Ok(Input::tcp(self.target.clone())),
+            Kind::Can => Input::can(&self.target),
+            Kind::Pcap => {
+                if !matches!(self.format, Format::Yd | Format::Auto){
+                    anyhow::bail!("packet captures are only supported for format yd");
+                }
+                let (port, path) = self.target.split_once(':')
+                    .with_context(|| format!("invalid capture {}, expected PORT:PATH", self.target))?;
+                Input::pcap(Path::new(path), u16::from_str(port)
+                    .with_context(|| format!("invalid port {}", port))?)
+            }
+        }
+    }
+}
+
+impl FromStr for Spec{
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self>{
+        let (label, spec) = match s.split_once('='){
+            Some((label, spec)) if !label.contains(':') => (Some(label), spec),
+            _ => (None, s)
+        };
+        let (kind, target) = spec.split_once(':')
+            .with_context(|| format!("invalid input {}, expected [LABEL=]KIND[+FORMAT]:TARGET", s))?;
+        let (kind, format) = match kind.split_once('+'){
+            Some((kind, format)) => (kind, Format::from_str(format)?),
+            None => (kind, Format::Auto)
+        };
+        let kind = match kind{
+            "file" => Kind::File,
+            "udp" => Kind::Udp,
+            "tcp" => Kind::Tcp,
+            "can" => Kind::Can,
+            "pcap" => Kind::Pcap,
+            _ => anyhow::bail!("unknown input kind {}, expected file, udp, tcp, can or pcap", kind),
+        };
+        Ok(Spec{
+            label: label.map(String::from).unwrap_or_else(|| spec.to_string()),
+            kind,
+            format,
+            target: target.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn detect(input: &[u8]) -> Result<Format>{
+        Input::Stream(Box::new(std::io::Cursor::new(input.to_vec()))).detect(Format::Auto).map(|(_, f)| f)
+    }
+
+    #[test]
+    fn detect_binary_formats(){
+        assert!(matches!(detect(&[0x10, 0x02, 0x93, 0x13, 0x02]).unwrap(), Format::Actisense));
+        assert!(matches!(detect(&[0x00, 0x10, 0x02, 0x93]).unwrap(), Format::Actisense));
+        assert!(matches!(detect(&[0xFF, 0xFF, 0x00, 0x00, 0x00, 0xE0, 0x59]).unwrap(), Format::Ydvr));
+    }
+
+    #[test]
+    fn detect_line_formats(){
+        let lines = [
+            ("17:33:21.141 R 09F80115 A0 7D E6 18 C0 05 FB D5", "Yd"),
+            ("(1623864801.141000) can0 09F80115#A07DE618C005FBD5", "Candump"),
+            ("2021-06-16-17:33:21.141,2,129025,21,255,8,a0,7d,e6,18,c0,05,fb,d5", "Canboat"),
+            ("$PCDIN,01F801,000C7E1B,15,A07DE618C005FBD5*23", "Pcdin"),
+            ("A173321.141 15FF2 1F801 A07DE618C005FBD5", "N2kAscii"),
+            ("$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230324,003.1,W*61", "Nmea0183"),
+        ];
+        for (line, expected) in lines{
+            let format = detect(format!("garbage\n{}\n", line).as_bytes()).unwrap();
+            assert_eq!(format!("{:?}", format), expected, "{}", line);
+        }
+    }
+
+    #[test]
+    fn detect_keeps_inspected_lines(){
+        let (input, _) = Input::Stream(Box::new(std::io::Cursor::new(
+            b"garbage\n17:33:21.141 R 09F80115 A0 7D E6 18 C0 05 FB D5\n".to_vec()))).detect(Format::Auto).unwrap();
+        assert_eq!(input.lines().unwrap().count(), 2);
+    }
+
+    #[test]
+    fn detect_unknown_format(){
+        assert!(detect(b"garbage\nmore garbage\n").is_err());
+        assert!(detect(b"").is_err());
+    }
+}
@@ -1,22 +1,57 @@
 //#![allow(dead_code,unused_imports)]
 mod state;
+mod vessels;
+mod collision;
+mod input;
 mod udpstream;
+mod tcpstream;
+mod replay;
+mod compress;
+mod pcap;
+#[cfg(target_os = "linux")]
+mod canstream;
 mod nmea;
 
 use crate::state::State;
-use crate::udpstream::UdpStream;
+use crate::collision::Alarms;
+use crate::input::{Format, Input, Spec, Kind, Values};
 use crate::nmea::nmea2000;
 
-use std::fs::File;
-use std::io::{BufReader, BufRead, BufWriter, Write};
-use std::path::PathBuf;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::thread;
-use std::sync::{Arc,Mutex};
+use std::sync::{Arc,Mutex,mpsc};
 use std::time::Duration;
 
 use structopt::StructOpt;
 use anyhow::{Context, Result};
 
+/// Format of the output
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat{
+    /// State as CSV document
+    Csv,
+    /// Input frames as candump log, i.e., a conversion of the input
+    Candump,
+}
+
+impl OutputFormat{
+    const VARIANTS: [&'static str; 2] = ["csv", "candump"];
+}
+
+impl FromStr for OutputFormat{
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self>{
+        match s{
+            "csv" => Ok(OutputFormat::Csv),
+            "candump" => Ok(OutputFormat::Candump),
+            _ => anyhow::bail!("unknown output format {}", s),
+        }
+    }
+}
+
 #[derive(Debug, StructOpt)]
 #[structopt(name = format!("SailStats Logger"), 
             about = "NMEA logger for navigational messages.")]
@@ -25,10 +60,39 @@ struct Opt{
     #[structopt(short="f", long="file", name="INPUT", parse(from_os_str))]
     input_file: Option<PathBuf>,
     
+    /// Read the input file as pcap or pcapng capture of the UDP packets to PORT, e.g., written by tcpdump
+    #[structopt(long, name="PORT", requires="INPUT")]
+    pcap: Option<u16>,
+
     /// Listen to port for incoming packets [default: 1457]
     #[structopt(short, long, conflicts_with="INPUT")]
     port: Option<u16>,
 
+    /// Read from SocketCAN interface, e.g., can0
+    #[structopt(short, long="can", name="INTERFACE", conflicts_with_all=&["INPUT","port"])]
+    can: Option<String>,
+
+    /// Connect to TCP server, e.g., 192.168.4.1:1456. Reconnects if the connection drops
+    #[structopt(short, long, name="ADDRESS", conflicts_with_all=&["INPUT","port","INTERFACE"])]
+    tcp: Option<String>,
+
+    /// Raw format of the input file or packets, detected from the first lines by default
+    #[structopt(short="F", long, default_value="auto", possible_values=&Format::VARIANTS, conflicts_with="INTERFACE")]
+    format: Format,
+
+    /// Additional input in the form [LABEL=]KIND[+FORMAT]:TARGET with KIND one of file, udp, tcp, can or pcap,
+    /// e.g., gw2=udp:1458 or mux=file+nmea0183:/dev/ttyUSB0. Can be given several times
+    #[structopt(short="I", long="input", name="SPEC", number_of_values=1)]
+    inputs: Vec<Spec>,
+
+    /// Replay the input file at SPEED times the recorded speed, e.g., 1 for real time
+    #[structopt(short, long, name="SPEED", requires="INPUT")]
+    replay: Option<f64>,
+
+    /// Send the lines of a replayed input file as UDP packets to ADDRESS, e.g., 255.255.255.255:1457
+    #[structopt(short, long, name="BROADCAST_ADDRESS", requires="SPEED")]
+    broadcast: Option<String>,
+
     /// Interval at which status line is printed in milliseconds when listening for packets
     #[structopt(short, long, default_value="250")]
     interval: u64,
@@ -36,36 +100,76 @@ struct Opt{
     /// Output filename
     #[structopt(short="o", long="output", name="OUTPUT", parse(from_os_str))]
     output_file: Option<PathBuf>,
+
+    /// Output filename for the position reports of AIS targets
+    #[structopt(short="a", long="ais", name="AIS_OUTPUT", parse(from_os_str))]
+    ais_file: Option<PathBuf>,
+
+    /// Raise a collision alarm on stderr if an AIS target comes closer than NM nautical miles
+    #[structopt(long, name="NM")]
+    cpa: Option<f32>,
+
+    /// Time to the closest point of approach in minutes within which collision alarms are raised [default: 15]
+    #[structopt(long, name="MINUTES", requires="NM")]
+    tcpa: Option<f32>,
+
+    /// Event log filename for the collision alarms
+    #[structopt(long="events", name="EVENT_LOG", parse(from_os_str), requires="NM")]
+    event_file: Option<PathBuf>,
+
+    /// Number of engine instances that are written, e.g., 1 for a single engine
+    #[structopt(long, name="ENGINES", default_value="2")]
+    engines: usize,
+
+    /// Number of fuel tank instances that are written
+    #[structopt(long, name="TANKS", default_value="2")]
+    fuel_tanks: usize,
+
+    /// Number of battery instances that are written, e.g., 3 for house, start and thruster bank
+    #[structopt(long, name="BATTERIES", default_value="2")]
+    batteries: usize,
+
+    /// Format of the output. `candump` converts the frames of an input file
+    #[structopt(short="O", long, default_value="csv", possible_values=&OutputFormat::VARIANTS)]
+    output_format: OutputFormat,
     
     /// Use date values that come from systime.
     #[structopt(short, long)]
     sys_date: bool,
 }
 
-fn read_thread<T,U>(
-        reader: BufReader<T>, 
-        parser: &mut nmea2000::Parser<U,String>, 
+fn read_thread(
+        input: Input,
+        source: &Spec,
+        replay: Option<f64>,
+        broadcast: Option<String>,
         state: Arc<Mutex<State>>) -> Result<()>
-    where
-        T: std::io::Read,
-        U: nmea::nmea2000::Raw + nmea::nmea2000::From<String> + Send,
     {
-        for line in reader.lines(){
-            if let Some(message) = parser.parse(&line.context("error processing line")?)
-                                        .context("error parsing line")?{
-                state.lock().unwrap().update(message);
+        let (mut input, format) = input.detect(source.format)?;
+        if let Some(speed) = replay{
+            input = input.replay(format, speed)?;
+            if let Some(address) = broadcast{
+                input = input.broadcast(&address)?;
             }
         }
+        let label = &source.label;
+        for v in input.values(format)?{
+            state.lock().unwrap().update_from(label, v?);
+        }
         Ok(())
 }
 
-fn write_thread<T: Write>(
+fn write_thread<T: Write, A: Write, E: Write>(
         writer: &mut BufWriter<T>, 
+        mut ais: Option<&mut BufWriter<A>>,
+        mut events: Option<&mut BufWriter<E>>,
         state: Arc<Mutex<State>>,
-        interval: u64) -> Result<()>
+        interval: u64,
+        stop: mpsc::Receiver<()>) -> Result<()>
     {
         //Write the headline
-        writer.write_all(format!("{}\n",State::headline()).as_bytes())
+        let headline = state.lock().unwrap().headline();
+        writer.write_all(format!("{}\n",headline).as_bytes())
             .context("unable to write headline")?;
         writer.flush()?; 
         
@@ -73,19 +177,152 @@ fn write_thread<T: Write>(
         let mut timestamp = s.timestamp;
         drop(s);
 
-        //Main writing loop
-        loop{
-            let s = state.lock().unwrap();
+        //Main writing loop, ends with a last write once stopped
+        let mut stopped = false;
+        while !stopped{
+            stopped = !matches!(stop.recv_timeout(Duration::from_millis(interval)),
+                                Err(mpsc::RecvTimeoutError::Timeout));
+            let mut s = state.lock().unwrap();
             //Write only on state change
             if timestamp != s.timestamp {
                 writer.write_all(format!("{}", s).as_bytes())
                     .context("error writing output")?;
                 writer.flush()?;
             }
+            if let Some(w) = ais.as_mut(){
+                s.write_reports(w).context("error writing AIS output")?;
+                w.flush()?;
+            }
+            s.write_events(events.as_deref_mut()).context("error writing event log")?;
+            if let Some(w) = events.as_mut(){
+                w.flush()?;
+            }
             timestamp = s.timestamp;
-            drop(s);
-            thread::sleep(Duration::from_millis(interval));
         }
+        Ok(())
+}
+
+/// Reads a file and writes every frame as candump log line. The date is taken from the frame if
+/// the format holds it, otherwise from the latest date message of the file. Frames before the first
+/// date message are kept until the date is known.
+fn convert_file<T,U,I,W>(
+        input: I,
+        mut state: State,
+        writer: &mut BufWriter<W>) -> Result<()>
+    where
+        I: Iterator<Item=std::io::Result<U>>,
+        T: nmea2000::Raw + nmea2000::From<U>,
+        W: Write,
+    {
+        let mut parser = nmea2000::Parser::<T,U>::new();
+        let mut pending: Vec<T> = Vec::new();
+
+        let mut write = |raw: &T, days: u16| -> Result<()>{
+            let line = nmea2000::candump::to_line(raw, days)
+                .context("unable to convert frame")?;
+            writer.write_all(format!("{}\n", line).as_bytes())
+                .context("error writing output")
+        };
+        let seconds = |raw: &T| { let (h, m, s) = raw.timestamp(); h as f32 * 3600.0 + m as f32 * 60.0 + s };
+
+        for item in input{
+            let raw = T::from(&item.context("error reading input")?)
+                .context("error parsing input")?;
+            if let Some(message) = parser.parse_from_raw(&raw)
+                .context("error parsing input")?{
+                state.update(message.values());
+            }
+            let days = match raw.date(){
+                Some(days) => days,
+                None if state.got_nmea_date => state.days,
+                None => {
+                    pending.push(raw);
+                    continue;
+                }
+            };
+            for p in pending.drain(..){
+                //Received before midnight of the first date
+                let before_midnight = seconds(&p) - seconds(&raw) > 43_200.0;
+                write(&p, if before_midnight { days.saturating_sub(1) } else { days })?;
+            }
+            write(&raw, days)?;
+        }
+        if !pending.is_empty(){
+            anyhow::bail!("no date in the input, unable to convert {} frames", pending.len());
+        }
+        writer.flush()?;
+        Ok(())
+}
+
+/// Converts `input` in `format` into a candump log, see [`convert_file`].
+fn convert<W: Write>(
+        input: Input,
+        format: Format,
+        state: State,
+        writer: &mut BufWriter<W>) -> Result<()>
+    {
+        match format{
+            Format::Yd => 
+                convert_file::<nmea2000::yd::Raw,_,_,_>(input.lines()?, state, writer),
+            Format::Actisense => 
+                convert_file::<nmea2000::actisense::Raw,_,_,_>(
+                    nmea2000::actisense::Reader::new(input.stream()?), state, writer),
+            Format::Candump => 
+                convert_file::<nmea2000::candump::Raw,_,_,_>(input.lines()?, state, writer),
+            Format::Canboat => 
+                convert_file::<nmea2000::canboat::Raw,_,_,_>(input.lines()?, state, writer),
+            Format::Pcdin => 
+                convert_file::<nmea2000::pcdin::Raw,_,_,_>(input.lines()?, state, writer),
+            Format::N2kAscii => 
+                convert_file::<nmea2000::n2kascii::Raw,_,_,_>(input.lines()?, state, writer),
+            Format::Ydvr => 
+                convert_file::<nmea2000::socketcan::Raw,_,_,_>(
+                    nmea2000::ydvr::Reader::new(input.stream()?), state, writer),
+            Format::Nmea0183 => 
+                anyhow::bail!("candump output is not supported for NMEA 0183"),
+            Format::Auto => 
+                anyhow::bail!("the format of the input has to be detected first"),
+        }
+}
+
+/// Reads a file and writes the state after every received message.
+fn read_file<W: Write, A: Write, E: Write>(
+        values: Values,
+        mut state: State,
+        writer: &mut BufWriter<W>,
+        mut ais: Option<&mut BufWriter<A>>,
+        mut events: Option<&mut BufWriter<E>>) -> Result<()>
+    {
+        //Write the headline
+        writer.write_all(format!("{}\n",state.headline()).as_bytes())
+            .context("unable to write headline")?;
+        writer.flush()?; 
+
+        for v in values{
+            state.update(v?);
+            writer.write_all(format!("{}", state).as_bytes())
+                .context("error writing output")?;
+            if let Some(w) = ais.as_mut(){
+                state.write_reports(w).context("error writing AIS output")?;
+            }
+            state.write_events(events.as_deref_mut()).context("error writing event log")?;
+        }
+        writer.flush()?;
+        if let Some(w) = ais{
+            w.flush()?;
+        }
+        if let Some(w) = events{
+            w.flush()?;
+        }
+        Ok(())
+}
+
+/// Creates the output file `f`, compressed according to its extension, and
+/// returns a buffered writer.
+fn create_output(f: &Path) -> Result<BufWriter<Box<dyn Write+Send>>>{
+    let file = std::fs::File::create(f)
+        .with_context(|| format!("could not create file {}", f.display()))?;
+    Ok(BufWriter::new(compress::writer(f, file)))
 }
 
 fn main() -> Result<()> {
@@ -93,81 +330,137 @@ fn main() -> Result<()> {
      * Program arguments
      **************************************************************************/
     let opt = Opt::from_args();
-    let in_stream: Box<dyn std::io::Read+Send>;
-    let out_stream: Box<dyn std::io::Write+Send>;
-    let reading_from_file: bool;
-    let mut sys_date: bool = opt.sys_date; // Can be overwritten if reading from file
+    let mut sources: Vec<Spec> = Vec::new();
     
     //Input args
-    if let Some(f) = opt.input_file{
-        in_stream = Box::new(
-                        File::open(f.to_str().unwrap())
-                            .with_context(|| format!("unable to open {}",f.to_str().unwrap()))?
-                    );
-        reading_from_file = true;
-        sys_date = false;
-    } else{
-        let port = match opt.port {
-                    Some(port) => port.to_string(),
-                    None => "1457".to_string(),
-                };
-        let address = format!("0.0.0.0:{}",port);
-        in_stream = Box::new(
-                        UdpStream::open(address.clone())
-                            .with_context(|| format!("could not open UDP listener on {}",address))?
-                    );
-        reading_from_file = false;
+    if let (Some(f), Some(port)) = (&opt.input_file, opt.pcap){
+        sources.push(Spec{label: format!("pcap:{}:{}", port, f.display()), kind: Kind::Pcap,
+                          format: opt.format, target: format!("{}:{}", port, f.display())});
+    } else if let Some(f) = opt.input_file{
+        sources.push(Spec{label: format!("file:{}", f.display()), kind: Kind::File, 
+                          format: opt.format, target: f.to_string_lossy().into_owned()});
+    } else if let Some(interface) = opt.can{
+        sources.push(Spec{label: format!("can:{}", interface), kind: Kind::Can, 
+                          format: opt.format, target: interface});
+    } else if let Some(address) = opt.tcp{
+        sources.push(Spec{label: format!("tcp:{}", address), kind: Kind::Tcp, 
+                          format: opt.format, target: address});
+    } else if opt.port.is_some() || opt.inputs.is_empty(){
+        let port = opt.port.unwrap_or(1457);
+        sources.push(Spec{label: format!("udp:{}", port), kind: Kind::Udp, 
+                          format: opt.format, target: port.to_string()});
+    }
+    sources.extend(opt.inputs);
+
+    //Files are read as fast as possible, all other sources are read like a network stream
+    let replay = opt.replay.is_some();
+    if replay && sources.len() > 1{
+        anyhow::bail!("replay is only supported for a single input file");
+    }
+    if opt.replay.map(|speed| speed <= 0.0).unwrap_or(false){
+        anyhow::bail!("replay speed has to be positive");
+    }
+    let live = replay || sources.len() > 1 || sources[0].is_live();
+    if sources.len() > 1 && sources.iter().any(|s| !s.is_live()){
+        anyhow::bail!("several inputs can only be read together from devices or the network");
     }
 
     //Output args
-    if let Some(f) = opt.output_file{
-        out_stream = Box::new(
-            File::create(f.to_str().unwrap())
-                .with_context(|| format!("could not create file {}", f.to_str().unwrap()))?
-        );
-    }else{
-        out_stream = Box::new(std::io::stdout());
+    let mut writer = match opt.output_file{
+        Some(f) => create_output(&f)?,
+        None => BufWriter::new(Box::new(std::io::stdout()) as Box<dyn Write+Send>),
+    };
+    if (opt.ais_file.is_some() || opt.cpa.is_some()) && opt.output_format != OutputFormat::Csv{
+        anyhow::bail!("AIS output and collision alarms are only supported for csv output");
     }
+    let mut ais_writer = match opt.ais_file{
+        Some(f) => {
+            let mut w = create_output(&f)?;
+            writeln!(w, "{}", vessels::HEADLINE).context("unable to write AIS headline")?;
+            Some(w)
+        },
+        None => None,
+    };
+    let mut event_writer = match opt.event_file{
+        Some(f) => {
+            let mut w = create_output(&f)?;
+            writeln!(w, "{}", collision::HEADLINE).context("unable to write event log headline")?;
+            Some(w)
+        },
+        None => None,
+    };
 
     /**************************************************************************
      * Main Program logic
      **************************************************************************/
-    let reader = BufReader::new(in_stream);
-    let mut writer = BufWriter::new(out_stream);
+    //Date from files can't come from the system
+    let mut state = State::new(opt.sys_date && live && !replay);
+    state.labels = sources.len() > 1;
+    state.instances(opt.engines, opt.fuel_tanks, opt.batteries);
+    state.vessels.track = ais_writer.is_some();
+    state.alarms = opt.cpa.map(|cpa| Alarms::new(cpa, opt.tcpa.unwrap_or(15.0)));
 
-    let mut parser = nmea2000::Parser::<nmea2000::yd::Raw,String>::new();
-    let mut state = State::new(sys_date);
+    if !live{
+        let source = &sources[0];
+        let (input, format) = source.open()?.detect(source.format)?;
+        return match opt.output_format{
+            OutputFormat::Csv => 
+                read_file(input.values(format)?, state, &mut writer, 
+                          ais_writer.as_mut(), event_writer.as_mut()),
+            OutputFormat::Candump => convert(input, format, state, &mut writer),
+        };
+    }
+    if opt.output_format == OutputFormat::Candump{
+        anyhow::bail!("candump output is only supported for input files");
+    }
 
-    if !reading_from_file{
-        let state_arc = Arc::new(Mutex::new(state));
+    let state_arc = Arc::new(Mutex::new(state));
+    let (done, finished) = mpsc::channel::<Result<()>>();
 
-        let writer_state = Arc::clone(&state_arc);
-        let writer_handle = thread::spawn(move || 
-            write_thread(&mut writer, writer_state, opt.interval)
-        );
+    let (stop, stopped) = mpsc::channel::<()>();
+    let writer_state = Arc::clone(&state_arc);
+    let writer_done = done.clone();
+    let writer_thread = thread::spawn(move || {
+        if let Err(e) = write_thread(&mut writer, ais_writer.as_mut(), event_writer.as_mut(),
+                                     writer_state, opt.interval, stopped){
+            let _ = writer_done.send(Err(e));
+        }
+        //Dropping the writers completes a compressed output
+        drop(writer);
+        drop(ais_writer);
+        drop(event_writer);
+    });
 
+    let mut running = sources.len();
+    for source in sources{
+        let input = source.open()?;
+        let broadcast = opt.broadcast.clone();
         let reader_state = Arc::clone(&state_arc);
-        let reader_handle = thread::spawn(move ||
-            read_thread(reader, &mut parser, reader_state)
+        let reader_done = done.clone();
+        //The format is detected in the reader thread, an input might not send anything yet
+        thread::spawn(move ||
+            reader_done.send(read_thread(input, &source, opt.replay, broadcast, reader_state)
+                                .with_context(|| format!("input {} failed", source.label)))
         );
-    
-        writer_handle.join().unwrap()?;
-        reader_handle.join().unwrap()?;
-    }else{
-        //Write the headline
-        writer.write_all(format!("{}\n",State::headline()).as_bytes())
-            .context("unable to write headline")?;
-        writer.flush()?; 
+    }
+    drop(done);
 
-        for line in reader.lines(){
-            if let Some(message) = parser.parse(&line.context("error processing line")?)
-                .context("error parsing line")?{
-                state.update(message);
-                writer.write_all(format!("{}", state).as_bytes())
-                    .context("error writing output")?;
-                writer.flush()?;
-            }
+    //Stop at the first error or if all inputs have ended
+    let mut result = Ok(());
+    for r in &finished{
+        if r.is_err(){
+            result = r;
+            break;
+        }
+        running -= 1;
+        if running == 0{
+            break;
         }
     }
-    Ok(())
+
+    //Let the writer write the final state and complete the output
+    drop(stop);
+    writer_thread.join().expect("writer thread panicked");
+    result?;
+    finished.try_iter().collect()
 }
@@ -1,20 +1,61 @@
+pub mod nmea0183;
 pub mod nmea2000;
 pub mod types;
 
+/// Parses some source type `U`, e.g., a line or a CAN frame, into [`MessageValue`]s.
+/// 
+/// Implemented by the parsers of the different NMEA standards.
+pub trait Parse<U>{
+    type Error: std::error::Error + Send + Sync + 'static;
 
+    /// Returns `Ok(Some(values))` if a complete message was received by this source.
+    fn parse_values(&mut self, src: &U) -> Result<Option<Vec<MessageValue>>, Self::Error>;
+}
+
+
+/// Value with the precision of its encoding. A value that is not available, e.g., marked by
+/// a reserved value in a NMEA 2000 field, is NaN.
 pub enum Float{
     F16(f32),
     F32(f32),
     F64(f64)
 }
 
+/// Temperature source of sea water
+pub const TEMPERATURE_SEA: u8 = 0;
+/// Temperature source of outside air
+pub const TEMPERATURE_OUTSIDE: u8 = 1;
+/// Humidity source of outside air
+pub const HUMIDITY_OUTSIDE: u8 = 1;
+
+/// Reference of a true heading
+pub const HEADING_TRUE: u8 = 0;
+/// Reference of a magnetic heading
+pub const HEADING_MAGNETIC: u8 = 1;
+
+/// Fluid type of fuel tanks
+pub const FLUID_FUEL: u8 = 0;
+
 /// Value of a NMEA message
 pub enum MessageValue{
-    WindAngle(Float),
-    WindSpeed(Float),
+    WindAngle(Float), //Apparent wind angle, rad
+    WindSpeed(Float), //Apparent wind speed, m/s
+    TrueWindAngle(Float), //True wind angle referenced to the water, rad
+    TrueWindSpeed(Float), //True wind speed referenced to the water, m/s
+    GroundWindAngle(Float), //True wind angle referenced to the ground, rad
+    GroundWindSpeed(Float), //True wind speed referenced to the ground, m/s
+    WindDirection(u8, Float), //Heading reference, direction of the ground referenced wind in rad
     Latitude(Float),
     Longitude(Float),
-    Heading(Float),
+    GnssMethod(u8), //Fix type, 0 no fix, 1 GNSS, 2 DGNSS, 3 precise, 4 RTK fixed, 5 RTK float, 6 estimated
+    Satellites(u8), //Number of satellites used in the fix
+    SatellitesInView(u8),
+    Hdop(Float),
+    Pdop(Float),
+    Vdop(Float),
+    GeoidalSeparation(Float), //Meters
+    Heading(u8, Float), //Heading reference, rad
+    Variation(Float), //Magnetic variation in rad, east is positive
     CourseOverGround(Float),
     SpeedOverGround(Float),
     SpeedThroughWater(Float),
@@ -23,8 +64,46 @@ pub enum MessageValue{
     Pitch(Float),
     Roll(Float),
     RudderAngle(Float),
+    Depth(Float), //Meters below waterline or keel
+    Log(Float), //Total distance through water in meters
+    CrossTrackError(Float), //Meters, positive to the right of the course
+    DistanceToWaypoint(Float), //Meters
+    BearingToWaypoint(u8, Float), //Heading reference, rad
+    WaypointClosingVelocity(Float), //m/s
+    DestinationWaypoint(u32), //Waypoint number
+    Waypoint(u32, String), //Waypoint number, name
+    TripLog(Float), //Trip distance through water in meters
+    Temperature(u8, Float), //Temperature source, Kelvin
+    Humidity(u8, Float), //Humidity source, percent
+    Pressure(Float), //Pascal
+    EngineSpeed(u8, Float), //Engine instance, revolutions per minute
+    CoolantTemperature(u8, Float), //Engine instance, Kelvin
+    FuelRate(u8, Float), //Engine instance, liters per hour
+    FluidLevel(u8, u8, Float), //Fluid type, tank instance, percent
+    BatteryVoltage(u8, Float), //Battery instance, Volt
+    BatteryCurrent(u8, Float), //Battery instance, Ampere
+    BatteryTemperature(u8, Float), //Battery instance, Kelvin
+    StateOfCharge(u8, Float), //Battery instance, percent
+    AisPosition(u32, Float, Float, Float, Float), //MMSI, latitude, longitude, COG in rad, SOG in m/s
+    AisName(u32, String), //MMSI, name of the vessel
+    AisCallsign(u32, String), //MMSI, call sign of the vessel
     Timestamp(types::Timestamp),
     Date(u16), //Days since 1.1.1970
     Time(f32), //Seonds since midnight
     LocalOffset(i16) //Local offset in minutes
-}
\ No newline at end of file
+}
+
+/// Converts seconds since January 1 1970 into a [`types::Timestamp`], i.e., into the 
+/// time of the day (UTC).
+pub fn timestamp_from_secs(secs: f64) -> types::Timestamp{
+    let s = secs.rem_euclid(86_400.0);
+    let hours = (s / 3600.0) as u8;
+    let minutes = ((s % 3600.0) / 60.0) as u8;
+    (hours, minutes, (s % 60.0) as f32)
+}
+
+/// Converts seconds since January 1 1970 into the days since January 1 1970, i.e., into the
+/// date (UTC) of a [`types::Timestamp`] from [`timestamp_from_secs`].
+pub fn days_from_secs(secs: f64) -> u16{
+    secs.div_euclid(86_400.0) as u16
+}
@@ -0,0 +1,435 @@
+//! Tools to read NMEA 0183 sentences.
+//!
+//! NMEA 0183 sentence format:
+//!
+//!  `$ttsss,f1,f2,...*hh<CR><LF>`
+//!
+//!  where:
+//!
+//!  • tt — talker identifier, e.g., `GP` for a GPS receiver or `II` for integrated instrumentation
+//!
+//!  • sss — sentence type, e.g., `RMC`
+//!
+//!  • f1,f2,... — comma separated data fields, fields may be empty if no data is available
+//!
+//!  • hh — checksum, XOR of all characters between `$` and `*` in hexadecimal format
+//!
+//! Supported sentences are RMC, GGA, VTG, MWV, MWD, HDG, HDT, VHW, DPT, XDR, ROT and RSA. All values
+//! are converted to the units used by NMEA 2000, i.e., radians and m/s.
+use crate::nmea::{MessageValue, Parse, HEADING_MAGNETIC, HEADING_TRUE, timestamp_from_secs};
+use crate::nmea::Float::*;
+use crate::nmea::MessageValue::*;
+
+use std::f32::consts::PI;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use thiserror::Error;
+
+/// Helper function to convert between degrees and radians
+#[inline(always)]
+fn to_radians(val: f32) -> f32{
+    val * PI / 180.0
+}
+
+/// Helper function to convert between knots and m/s
+#[inline(always)]
+fn from_knots(val: f32) -> f32{
+    val / 1.943_844_6
+}
+
+/// Parser for NMEA 0183 sentences
+///
+/// Sentences are independent from each other, i.e., every supported sentence yields
+/// a complete message.
+#[derive(Default)]
+pub struct Parser{}
+
+impl Parser{
+    /// Returns a new [`Parser`]
+    pub fn new() -> Self{
+        Parser{}
+    }
+
+    /// Parses a sentence, returns `Ok(None)` for unsupported and corrupted sentences.
+    pub fn parse(&mut self, s: &str) -> Result<Option<Vec<MessageValue>>, NMEA0183Error>{
+        let s = s.trim();
+        //Empty lines, encapsulated sentences, e.g., AIS, and fragments of sentences, e.g., the
+        //first line read from a serial port opened in the middle of a sentence
+        if !s.starts_with('$') {
+            return Ok(None);
+        }
+        //Corrupted on the way, e.g., a checksum mismatch, truncated or garbled fields, just drop it
+        Ok(Sentence::from(s).and_then(|fields| fields.values()).unwrap_or(None))
+    }
+}
+
+impl Parse<String> for Parser{
+    type Error = NMEA0183Error;
+
+    fn parse_values(&mut self, src: &String) -> Result<Option<Vec<MessageValue>>, NMEA0183Error>{
+        self.parse(src)
+    }
+}
+
+/// Fields of a sentence with valid checksum
+struct Sentence<'a>{
+    address: &'a str,
+    fields: Vec<&'a str>
+}
+
+impl<'a> Sentence<'a>{
+    /// Returns the values of the sentence, `None` for unsupported sentences
+    fn values(&self) -> Result<Option<Vec<MessageValue>>, NMEA0183Error>{
+        let values = match self.sentence_type(){
+            "RMC" => self.rmc()?,
+            "GGA" => self.gga()?,
+            "VTG" => self.vtg()?,
+            "MWV" => self.mwv()?,
+            "MWD" => self.mwd()?,
+            "HDG" => self.hdg()?,
+            "HDT" => self.hdt()?,
+            "VHW" => self.vhw()?,
+            "DPT" => self.dpt()?,
+            "XDR" => self.xdr()?,
+            "ROT" => self.rot()?,
+            "RSA" => self.rsa()?,
+            _ => return Ok(None)
+        };
+        Ok(Some(values))
+    }
+
+    /// Splits a sentence into its fields and validates the checksum if present.
+    fn from(s: &'a str) -> Result<Self, NMEA0183Error>{
+        let s = s.strip_prefix('$').ok_or(NMEA0183Error::InvalidSentence)?;
+        let s = match s.split_once('*'){
+            Some((s, checksum)) => {
+                let checksum = u8::from_str_radix(checksum, 16)?;
+                if s.bytes().fold(0, |c, b| c ^ b) != checksum {
+                    return Err(NMEA0183Error::ChecksumError);
+                }
+                s
+            }
+            None => s
+        };
+        let mut fields = s.split(',');
+        let address = fields.next().ok_or(NMEA0183Error::InvalidSentence)?;
+        if !address.is_ascii() {
+            return Err(NMEA0183Error::InvalidSentence);
+        }
+        Ok(Sentence{address, fields: fields.collect()})
+    }
+
+    /// Sentence type without talker identifier, empty for proprietary sentences
+    fn sentence_type(&self) -> &str{
+        match self.address.len(){
+            5 if !self.address.starts_with('P') => &self.address[2..],
+            _ => ""
+        }
+    }
+
+    /// Field `i`, empty if not present
+    fn field(&self, i: usize) -> &str{
+        self.fields.get(i).copied().unwrap_or("")
+    }
+
+    /// Field `i` as number, `None` if empty
+    fn float(&self, i: usize) -> Result<Option<f32>, NMEA0183Error>{
+        match self.field(i){
+            "" => Ok(None),
+            f => Ok(Some(f32::from_str(f)?))
+        }
+    }
+
+    /// Time `hhmmss.ss` in field `i` as seconds since midnight
+    fn time(&self, i: usize) -> Result<Option<f64>, NMEA0183Error>{
+        let t = self.field(i);
+        if t.is_empty() {
+            return Ok(None);
+        }
+        if t.len() < 6 || !t.is_ascii() {
+            return Err(NMEA0183Error::InvalidSentence);
+        }
+        Ok(Some(f64::from_str(&t[0..2])? * 3600.0
+                + f64::from_str(&t[2..4])? * 60.0
+                + f64::from_str(&t[4..])?))
+    }
+
+    /// Latitude `ddmm.mm` or longitude `dddmm.mm` in field `i` with hemisphere in
+    /// field `i+1` as degrees
+    fn coordinate(&self, i: usize) -> Result<Option<f64>, NMEA0183Error>{
+        let c = self.field(i);
+        if c.is_empty() {
+            return Ok(None);
+        }
+        let dot = c.find('.').unwrap_or(c.len());
+        if dot < 2 || !c.is_ascii() {
+            return Err(NMEA0183Error::InvalidSentence);
+        }
+        let degrees = f64::from_str(&c[..dot-2])?;
+        let minutes = f64::from_str(&c[dot-2..])?;
+        let value = degrees + minutes / 60.0;
+        match self.field(i+1){
+            "N" | "E" => Ok(Some(value)),
+            "S" | "W" => Ok(Some(-value)),
+            _ => Err(NMEA0183Error::InvalidSentence)
+        }
+    }
+
+    /// Angle in degrees in field `i` with direction `E` or `W` in field `i+1` as radians,
+    /// west is negative
+    fn east_west(&self, i: usize) -> Result<Option<f32>, NMEA0183Error>{
+        Ok(match (self.float(i)?, self.field(i+1)){
+            (Some(v), "E") => Some(to_radians(v)),
+            (Some(v), "W") => Some(-to_radians(v)),
+            _ => None
+        })
+    }
+
+    /// Speed in field `i` with unit in field `i+1` in m/s
+    fn speed(&self, i: usize) -> Result<Option<f32>, NMEA0183Error>{
+        Ok(match (self.float(i)?, self.field(i+1)){
+            (Some(v), "N") => Some(from_knots(v)),
+            (Some(v), "M") => Some(v),
+            (Some(v), "K") => Some(v / 3.6),
+            (Some(v), "S") => Some(v * 0.447_04),
+            _ => None
+        })
+    }
+
+    /// Recommended minimum data: time, date, position, COG and SOG
+    fn rmc(&self) -> Result<Vec<MessageValue>, NMEA0183Error>{
+        let mut values = Vec::new();
+        if let Some(time) = self.time(0)? {
+            let d = self.field(8);
+            if d.len() == 6 && d.is_ascii() {
+                let date = NaiveDate::from_ymd_opt(2000 + i32::from_str(&d[4..6])?,
+                                                   u32::from_str(&d[2..4])?,
+                                                   u32::from_str(&d[0..2])?)
+                    .ok_or(NMEA0183Error::InvalidSentence)?;
+                let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+                let days = date.signed_duration_since(epoch).num_days();
+                //Date and time are UTC
+                values.push(Date(days as u16));
+                values.push(Time(time as f32));
+                values.push(LocalOffset(0));
+            }
+            values.push(Timestamp(timestamp_from_secs(time)));
+        }
+        if self.field(1) == "A" {
+            if let (Some(lat), Some(long)) = (self.coordinate(2)?, self.coordinate(4)?) {
+                values.push(Latitude(F64(lat)));
+                values.push(Longitude(F64(long)));
+            }
+            if let Some(sog) = self.float(6)? {
+                values.push(SpeedOverGround(F16(from_knots(sog))));
+            }
+            if let Some(cog) = self.float(7)? {
+                values.push(CourseOverGround(F16(to_radians(cog))));
+            }
+            if let Some(variation) = self.east_west(9)? {
+                values.push(Variation(F16(variation)));
+            }
+        }
+        Ok(values)
+    }
+
+    /// GPS fix data: time, position, fix quality, satellites, HDOP and geoidal separation.
+    /// The fix quality matches the GNSS method of NMEA 2000
+    fn gga(&self) -> Result<Vec<MessageValue>, NMEA0183Error>{
+        let mut values = Vec::new();
+        if let Some(time) = self.time(0)? {
+            values.push(Time(time as f32));
+            values.push(Timestamp(timestamp_from_secs(time)));
+        }
+        if let Some(fix) = self.float(5)? {
+            values.push(GnssMethod(fix as u8));
+        }
+        if let Some(satellites) = self.float(6)? {
+            values.push(Satellites(satellites as u8));
+        }
+        if let Some(hdop) = self.float(7)? {
+            values.push(Hdop(F16(hdop)));
+        }
+        if let Some(separation) = self.float(10)? {
+            values.push(GeoidalSeparation(F32(separation)));
+        }
+        //Fix quality 0 denotes an invalid fix
+        if !matches!(self.field(5), "" | "0") {
+            if let (Some(lat), Some(long)) = (self.coordinate(1)?, self.coordinate(3)?) {
+                values.push(Latitude(F64(lat)));
+                values.push(Longitude(F64(long)));
+            }
+        }
+        Ok(values)
+    }
+
+    /// Track made good and ground speed
+    fn vtg(&self) -> Result<Vec<MessageValue>, NMEA0183Error>{
+        let mut values = Vec::new();
+        if let Some(cog) = self.float(0)? {
+            values.push(CourseOverGround(F16(to_radians(cog))));
+        }
+        if let Some(sog) = self.float(4)? {
+            values.push(SpeedOverGround(F16(from_knots(sog))));
+        }
+        Ok(values)
+    }
+
+    /// Wind speed and angle, relative (apparent) or theoretical (true, referenced to the water)
+    fn mwv(&self) -> Result<Vec<MessageValue>, NMEA0183Error>{
+        let mut values = Vec::new();
+        if self.field(4) != "A" {
+            return Ok(values);
+        }
+        let angle = self.float(0)?.map(|angle| F16(to_radians(angle)));
+        let speed = self.speed(2)?.map(F16);
+        match self.field(1){
+            "R" => {
+                values.extend(angle.map(WindAngle));
+                values.extend(speed.map(WindSpeed));
+            }
+            "T" => {
+                values.extend(angle.map(TrueWindAngle));
+                values.extend(speed.map(TrueWindSpeed));
+            }
+            _ => ()
+        }
+        Ok(values)
+    }
+
+    /// Wind direction and wind speed, referenced to the ground. The magnetic direction is only
+    /// used without a true direction
+    fn mwd(&self) -> Result<Vec<MessageValue>, NMEA0183Error>{
+        let mut values = Vec::new();
+        match (self.float(0)?, self.field(1), self.float(2)?, self.field(3)) {
+            (Some(direction), "T", _, _) => 
+                values.push(WindDirection(HEADING_TRUE, F16(to_radians(direction)))),
+            (_, _, Some(direction), "M") => 
+                values.push(WindDirection(HEADING_MAGNETIC, F16(to_radians(direction)))),
+            _ => ()
+        }
+        if let Some(speed) = self.speed(4)?.or(self.speed(6)?) {
+            values.push(GroundWindSpeed(F16(speed)));
+        }
+        Ok(values)
+    }
+
+    /// Heading, deviation and variation, the sensor heading corrected by the deviation is the
+    /// magnetic heading
+    fn hdg(&self) -> Result<Vec<MessageValue>, NMEA0183Error>{
+        let mut values = Vec::new();
+        //The variation comes first to derive the true heading
+        if let Some(variation) = self.east_west(3)? {
+            values.push(Variation(F16(variation)));
+        }
+        if let Some(hdg) = self.float(0)? {
+            let deviation = self.east_west(1)?.unwrap_or(0.0);
+            values.push(Heading(HEADING_MAGNETIC, F16(to_radians(hdg) + deviation)));
+        }
+        Ok(values)
+    }
+
+    /// Heading true
+    fn hdt(&self) -> Result<Vec<MessageValue>, NMEA0183Error>{
+        Ok(self.float(0)?.map(|hdg| vec![Heading(HEADING_TRUE, F16(to_radians(hdg)))]).unwrap_or_default())
+    }
+
+    /// Water speed and heading, only the speed is used
+    fn vhw(&self) -> Result<Vec<MessageValue>, NMEA0183Error>{
+        Ok(self.float(4)?.map(|stw| vec![SpeedThroughWater(F16(from_knots(stw)))]).unwrap_or_default())
+    }
+
+    /// Depth of water in meters below the transducer and offset of the transducer to the 
+    /// waterline (positive) or the keel (negative)
+    fn dpt(&self) -> Result<Vec<MessageValue>, NMEA0183Error>{
+        let offset = self.float(1)?.unwrap_or(0.0);
+        Ok(self.float(0)?.map(|depth| vec![Depth(F32(depth + offset))]).unwrap_or_default())
+    }
+
+    /// Transducer measurements, angular measurements named `PITCH`, `ROLL` or `HEEL` are used
+    fn xdr(&self) -> Result<Vec<MessageValue>, NMEA0183Error>{
+        let mut values = Vec::new();
+        for i in (0..self.fields.len()).step_by(4){
+            if self.field(i) != "A" || self.field(i+2) != "D" {
+                continue;
+            }
+            if let Some(angle) = self.float(i+1)? {
+                match self.field(i+3).to_ascii_uppercase().as_str(){
+                    "PITCH" | "PTCH" => values.push(Pitch(F16(to_radians(angle)))),
+                    "ROLL" | "HEEL" => values.push(Roll(F16(to_radians(angle)))),
+                    _ => ()
+                }
+            }
+        }
+        Ok(values)
+    }
+
+    /// Rate of turn in degrees per minute
+    fn rot(&self) -> Result<Vec<MessageValue>, NMEA0183Error>{
+        if self.field(1) != "A" {
+            return Ok(Vec::new());
+        }
+        Ok(self.float(0)?.map(|rot| vec![RateOfTurn(F32(to_radians(rot / 60.0)))]).unwrap_or_default())
+    }
+
+    /// Rudder sensor angle, only the starboard (or single) rudder is used
+    fn rsa(&self) -> Result<Vec<MessageValue>, NMEA0183Error>{
+        if self.field(1) != "A" {
+            return Ok(Vec::new());
+        }
+        Ok(self.float(0)?.map(|ra| vec![RudderAngle(F16(to_radians(ra)))]).unwrap_or_default())
+    }
+}
+
+#[derive(Error,Debug)]
+pub enum NMEA0183Error{
+    #[error("unknown sentence format")]
+    InvalidSentence,
+    #[error("checksum mismatch")]
+    ChecksumError,
+    #[error(transparent)]
+    ParseIntError(#[from] std::num::ParseIntError),
+    #[error(transparent)]
+    ParseFloatError(#[from] std::num::ParseFloatError),
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn parse(s: &str) -> Option<Vec<MessageValue>>{
+        Parser::new().parse(s).unwrap()
+    }
+
+    #[test]
+    fn rmc(){
+        let values = parse("$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230324,003.1,W*61").unwrap();
+        assert!(values.iter().any(|v| matches!(v, Latitude(F64(l)) if (l - 48.1173).abs() < 1e-6)));
+        assert!(values.iter().any(|v| matches!(v, Longitude(F64(l)) if (l - 11.516_666).abs() < 1e-6)));
+        assert!(values.iter().any(|v| matches!(v, Date(d) if *d == 19805)));
+        assert!(values.iter().any(|v| matches!(v, Variation(F16(v)) if *v < 0.0)));
+    }
+
+    #[test]
+    fn truncated_sentence(){
+        assert!(parse("$GPRMC,120000,A,4807.038,N,01131.0").is_none());
+    }
+
+    #[test]
+    fn garbled_sentence(){
+        assert!(parse("$GPRMC,12:00:00,A,48O7.038,N,01131.000,E,022.4,084.4,230394,003.1,W").is_none());
+        assert!(parse("$GPHDT,27#.07,T").is_none());
+    }
+
+    #[test]
+    fn checksum_mismatch(){
+        assert!(parse("$GPHDT,274.07,T*00").is_none());
+    }
+
+    #[test]
+    fn fragment(){
+        assert!(parse("PA,1.5,T,2.0,N,A*00").is_none());
+        assert!(parse("").is_none());
+    }
+}
@@ -0,0 +1,289 @@
+//! Tools to read the Actisense NGT-1 binary protocol. Implements the `N2kRaw` trait.
+//! 
+//! Actisense binary format:
+//! 
+//!  `DLE STX cmd len b0 .. bn crc DLE ETX`
+//! 
+//!  where:
+//! 
+//!  • DLE, STX, ETX — framing bytes `0x10`, `0x02` and `0x03`. A `0x10` within the message is escaped as `DLE DLE`
+//! 
+//!  • cmd — message type, `0x93` for NMEA 2000 messages received from the bus
+//! 
+//!  • len — number of data bytes b0..bn
+//! 
+//!  • crc — checksum byte, the sum of all bytes from cmd to crc is `0` (mod 256)
+//! 
+//! The data bytes of a `0x93` message are:
+//! 
+//!  `prio pgn0 pgn1 pgn2 dest src t0 t1 t2 t3 n d0 .. dn`
+//! 
+//!  where t0..t3 is the time in milliseconds since the NGT-1 was powered up and d0..dn 
+//!  are the n bytes of the complete NMEA 2000 message, i.e., fast packets are already reassembled.
+//!
+//! The time of the NGT-1 is synchronized with the time of day of the NMEA 2000 date messages, see
+//! [`Reader`].
+use std::collections::VecDeque;
+use std::io::{self, BufReader, Bytes, Read};
+
+use crate::nmea::types::{TData, TDest, TPgn, TPrio, TSrc, Timestamp};
+use crate::nmea::nmea2000;
+use crate::nmea::nmea2000::NMEA2000Error;
+use crate::nmea::timestamp_from_secs;
+
+use std::time::SystemTime;
+
+pub const DLE: u8 = 0x10;
+pub const STX: u8 = 0x02;
+const ETX: u8 = 0x03;
+
+/// Message type of NMEA 2000 messages received by the NGT-1
+pub const N2K_MSG_RECEIVED: u8 = 0x93;
+/// PGN of the System Time message
+const SYSTEM_TIME_PGN: u32 = 126992;
+/// PGN of the Time & Date message
+const TIME_DATE_PGN: u32 = 129033;
+/// Longest time in seconds of the NGT-1 that frames are held back until a date message is received
+const MAX_HOLD: f64 = 5.0;
+
+/// Unescaped frame `cmd len b0 .. bn crc` with the time of day it was received by the NGT-1
+pub struct Frame{
+    pub timestamp : Timestamp,
+    pub data : Vec<u8>
+}
+
+/// Holds an Actisense NMEA 2000 message.
+pub struct Raw{
+    /// Time the message was received by the NGT-1
+    pub timestamp : Timestamp,
+    pub prio : u8,
+    pub pgn : u32,
+    pub src : u8,
+    pub dest : u8,
+    pub data : Vec<u8>
+}
+
+impl nmea2000::Raw for Raw{
+    #[inline(always)]
+    fn timestamp(&self) -> Timestamp { self.timestamp }
+    #[inline(always)]
+    fn src(&self) -> TSrc { self.src }
+    #[inline(always)]
+    fn dest(&self) -> TDest { self.dest }
+    #[inline(always)]
+    fn prio(&self) -> TPrio { self.prio }
+    #[inline(always)]
+    fn pgn(&self) -> TPgn { self.pgn }
+    #[inline(always)]
+    fn data(&self) -> TData { self.data.clone() }
+
+    fn write(&self, m: &mut Box<dyn nmea2000::Message>) -> Result<(),NMEA2000Error>{
+        nmea2000::write_message(self, m)
+    }
+}
+
+/// Reads a `Raw` message from a [`Frame`] as returned by [`Reader`].
+impl nmea2000::From<Frame> for Raw{
+    fn from(frame: &Frame) -> Result<Self, NMEA2000Error>{
+        let f = &frame.data;
+        if f.len() < 2 || f[0] != N2K_MSG_RECEIVED || f.len() != f[1] as usize + 3 {
+            return Err(NMEA2000Error::RawFormatError);
+        }
+        if f.iter().fold(0u8, |crc, b| crc.wrapping_add(*b)) != 0 {
+            return Err(NMEA2000Error::ChecksumError);
+        }
+
+        let d = &f[2..f.len()-1];
+        if d.len() < 11 || d.len() != d[10] as usize + 11 {
+            return Err(NMEA2000Error::RawFormatError);
+        }
+
+        Ok(Raw{
+            timestamp: frame.timestamp,
+            prio: d[0],
+            pgn: u32::from_le_bytes([d[1],d[2],d[3],0]),
+            dest: d[4],
+            src: d[5],
+            data: d[11..].to_vec()
+        })
+    }
+}
+
+/// Decodes the DLE/STX framing of an Actisense byte stream, e.g., from a serial device or
+/// a captured file.
+/// 
+/// Iterates over the unescaped [`Frame`]s. Frames of other message types than [`N2K_MSG_RECEIVED`]
+/// are skipped.
+/// 
+/// The time of the NGT-1 is converted into the time of day with the latest date message. Frames
+/// before the first date message are held back until it is received. Without date messages, e.g.,
+/// from a NGT-1 on a bus without GNSS, the time of the system is used after [`MAX_HOLD`].
+pub struct Reader<R: Read>{
+    bytes: Bytes<BufReader<R>>,
+    /// Time of day minus the time of the NGT-1 in seconds, `None` until known
+    offset: Option<f64>,
+    /// Frames held back until the offset is known with the time of the NGT-1 in seconds
+    pending: VecDeque<(f64, Vec<u8>)>,
+}
+
+impl<R: Read> Reader<R>{
+    pub fn new(r: R) -> Self{
+        Reader{bytes: BufReader::new(r).bytes(), offset: None, pending: VecDeque::new()}
+    }
+
+    /// Returns the time of the NGT-1 of frame `f` in seconds
+    fn device_time(f: &[u8]) -> f64{
+        f.get(8..12).map(|t| u32::from_le_bytes([t[0],t[1],t[2],t[3]]) as f64 / 1000.0).unwrap_or(0.0)
+    }
+
+    /// Sets the offset from the time of day of a date message received at `time`
+    fn synchronize(&mut self, time: f64, f: &[u8]){
+        if f.len() < 14 || f.iter().fold(0u8, |crc, b| crc.wrapping_add(*b)) != 0 {
+            return;
+        }
+        let pgn = u32::from_le_bytes([f[3],f[4],f[5],0]);
+        let data = &f[13..f.len()-1];
+        let t = match (pgn, data.len()){
+            (SYSTEM_TIME_PGN, 8) => u32::from_le_bytes([data[4],data[5],data[6],data[7]]),
+            (TIME_DATE_PGN, 8) => u32::from_le_bytes([data[2],data[3],data[4],data[5]]),
+            _ => return,
+        };
+        //Time of the day in units of 0.0001s, values above are not available
+        if t < 864_000_000{
+            self.offset = Some(t as f64 * 0.0001 - time);
+        }
+    }
+
+    /// Uses the time of the system if no date message was received for frames received
+    /// until `time`
+    fn fallback(&mut self, time: f64){
+        if self.offset.is_none(){
+            let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap();
+            self.offset = Some(now.as_secs_f64() - time);
+        }
+    }
+
+    /// Returns the next frame with its time of day, `Ok(None)` at the end of the stream.
+    fn next_timed_frame(&mut self) -> io::Result<Option<Frame>>{
+        loop{
+            if let Some(offset) = self.offset{
+                if let Some((time, data)) = self.pending.pop_front(){
+                    return Ok(Some(Frame{timestamp: timestamp_from_secs(time + offset), data}));
+                }
+            }
+            match self.next_frame()?{
+                Some(f) => {
+                    let time = Reader::<R>::device_time(&f);
+                    self.synchronize(time, &f);
+                    self.pending.push_back((time, f));
+                    if self.pending.front().is_some_and(|(first, _)| time - first > MAX_HOLD){
+                        self.fallback(time);
+                    }
+                }
+                None => match self.pending.back(){
+                    Some(&(time, _)) => self.fallback(time),
+                    None => return Ok(None),
+                }
+            }
+        }
+    }
+
+    /// Reads the next frame, returns `Ok(None)` at the end of the stream.
+    fn next_frame(&mut self) -> io::Result<Option<Vec<u8>>>{
+        let mut frame = Vec::new();
+        let mut in_frame = false;
+        let mut escaped = false;
+        for b in self.bytes.by_ref(){
+            let b = b?;
+            if escaped{
+                escaped = false;
+                match b{
+                    STX => { frame.clear(); in_frame = true; }
+                    ETX if in_frame => {
+                        if frame.first() == Some(&N2K_MSG_RECEIVED){
+                            return Ok(Some(frame));
+                        }
+                        in_frame = false;
+                    }
+                    DLE if in_frame => frame.push(DLE),
+                    _ => in_frame = false
+                }
+            }else if b == DLE{
+                escaped = true;
+            }else if in_frame{
+                frame.push(b);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<R: Read> Iterator for Reader<R>{
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item>{
+        self.next_timed_frame().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::nmea::nmea2000::{From, Raw as _};
+
+    /// Returns the framed and escaped message of `pgn` received at `time` milliseconds
+    fn message(pgn: u32, time: u32, data: &[u8]) -> Vec<u8>{
+        let mut d = vec![2, pgn as u8, (pgn >> 8) as u8, (pgn >> 16) as u8, 255, 0x15];
+        d.extend_from_slice(&time.to_le_bytes());
+        d.push(data.len() as u8);
+        d.extend_from_slice(data);
+        let mut f = vec![N2K_MSG_RECEIVED, d.len() as u8];
+        f.extend(d);
+        f.push(0u8.wrapping_sub(f.iter().fold(0u8, |crc, b| crc.wrapping_add(*b))));
+        let mut bytes = vec![DLE, STX];
+        for b in f{
+            if b == DLE{
+                bytes.push(DLE);
+            }
+            bytes.push(b);
+        }
+        bytes.extend_from_slice(&[DLE, ETX]);
+        bytes
+    }
+
+    /// Time & Date message with the time of day 12:00:00
+    fn time_date() -> Vec<u8>{
+        let mut data = vec![0x6A, 0x49];
+        data.extend_from_slice(&(12u32 * 3600 * 10_000).to_le_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data
+    }
+
+    #[test]
+    fn parse_message(){
+        let mut bytes = message(129025, 1000, &[0x10, 0x7D, 0xE6, 0x18, 0xC0, 0x05, 0xFB, 0xD5]);
+        bytes.extend(message(129033, 2000, &time_date()));
+        let frames = Reader::new(&bytes[..]).collect::<io::Result<Vec<_>>>().unwrap();
+        assert_eq!(frames.len(), 2);
+        let raw = <Raw as From<Frame>>::from(&frames[0]).unwrap();
+        assert_eq!(raw.pgn(), 129025);
+        assert_eq!(raw.src(), 0x15);
+        assert_eq!(raw.data(), vec![0x10, 0x7D, 0xE6, 0x18, 0xC0, 0x05, 0xFB, 0xD5]);
+        //Received one second before the date message
+        assert_eq!(raw.timestamp(), (11, 59, 59.0));
+    }
+
+    #[test]
+    fn malformed_message(){
+        let mut bytes = message(129025, 1000, &[0; 8]);
+        bytes.extend(message(129033, 2000, &time_date()));
+        //Checksum
+        bytes[10] ^= 1;
+        let frames = Reader::new(&bytes[..]).collect::<io::Result<Vec<_>>>().unwrap();
+        assert!(matches!(<Raw as From<Frame>>::from(&frames[0]), Err(NMEA2000Error::ChecksumError)));
+        //Length, too short for a message
+        let crc = 0u8.wrapping_sub(N2K_MSG_RECEIVED + 2);
+        let frame = Frame{timestamp: (0, 0, 0.0), data: vec![N2K_MSG_RECEIVED, 2, 0, 0, crc]};
+        assert!(matches!(<Raw as From<Frame>>::from(&frame), Err(NMEA2000Error::RawFormatError)));
+    }
+}
@@ -0,0 +1,145 @@
+//! Tools to read the canboat "plain" format as used by the canboat `analyzer`. Implements the `N2kRaw` trait.
+//! 
+//! canboat plain format:
+//! 
+//!  `yyyy-mm-dd-hh:mm:ss.ddd,prio,pgn,src,dest,len,b0,b1,...`
+//! 
+//!  where:
+//! 
+//!  • yyyy-mm-dd-hh:mm:ss.ddd — date and time of reception, newer versions use `yyyy-mm-ddThh:mm:ss.dddZ`
+//! 
+//!  • prio, pgn, src, dest — priority, PGN, source and destination in decimal format
+//! 
+//!  • len — number of data bytes
+//! 
+//!  • b0.. — data bytes in hexadecimal format
+//! 
+//! Depending on the tool that wrote the file, fast packets are either already reassembled
+//! (more than 8 data bytes) or stored as single frames.
+use crate::nmea::types::{TData, TDest, TPgn, TPrio, TSrc, Timestamp};
+use crate::nmea::nmea2000;
+use crate::nmea::nmea2000::NMEA2000Error;
+
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+
+/// Holds a canboat plain message.
+pub struct Raw{
+    /// Days since January 1 1970
+    pub date : u16,
+    pub timestamp : Timestamp,
+    pub prio : u8,
+    pub pgn : u32,
+    pub src : u8,
+    pub dest : u8,
+    pub data : Vec<u8>
+}
+
+impl nmea2000::Raw for Raw{
+    #[inline(always)]
+    fn timestamp(&self) -> Timestamp { self.timestamp }
+    #[inline(always)]
+    fn src(&self) -> TSrc { self.src }
+    #[inline(always)]
+    fn dest(&self) -> TDest { self.dest }
+    #[inline(always)]
+    fn prio(&self) -> TPrio { self.prio }
+    #[inline(always)]
+    fn pgn(&self) -> TPgn { self.pgn }
+    #[inline(always)]
+    fn data(&self) -> TData { self.data.clone() }
+    #[inline(always)]
+    fn date(&self) -> Option<u16> { Some(self.date) }
+
+    fn write(&self, m: &mut Box<dyn nmea2000::Message>) -> Result<(),NMEA2000Error>{
+        if self.data.len() > 8 {
+            nmea2000::write_message(self, m)
+        } else {
+            nmea2000::write_frame(self, m)
+        }
+    }
+}
+
+impl nmea2000::From<String> for Raw{
+    fn from(s: &String) -> Result<Self, NMEA2000Error>{
+        // Split data fields
+        let mut fields = s.trim().split(',');
+
+        //Parse date and time
+        let t = fields.next().ok_or(NMEA2000Error::RawFormatError)?;
+        let d = t.get(0..10).filter(|d| d.is_ascii()).ok_or(NMEA2000Error::RawFormatError)?;
+        let date = NaiveDate::from_ymd_opt(i32::from_str(&d[0..4])?,
+                                           u32::from_str(&d[5..7])?,
+                                           u32::from_str(&d[8..10])?)
+            .ok_or(NMEA2000Error::RawFormatError)?;
+        let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+        let date = date.signed_duration_since(epoch).num_days() as u16;
+        let t = t.get(11..).ok_or(NMEA2000Error::RawFormatError)?;
+        let t = t.strip_suffix('Z').unwrap_or(t);
+        if t.len() < 8 || !t.is_ascii() {
+            return Err(NMEA2000Error::RawFormatError);
+        }
+        let timestamp = (
+            u8::from_str(&t[0..2])?,
+            u8::from_str(&t[3..5])?,
+            f32::from_str(&t[6..])?
+        );
+
+        let prio = u8::from_str(fields.next().ok_or(NMEA2000Error::RawFormatError)?)?;
+        let pgn = u32::from_str(fields.next().ok_or(NMEA2000Error::RawFormatError)?)?;
+        let src = u8::from_str(fields.next().ok_or(NMEA2000Error::RawFormatError)?)?;
+        let dest = u8::from_str(fields.next().ok_or(NMEA2000Error::RawFormatError)?)?;
+        let len = usize::from_str(fields.next().ok_or(NMEA2000Error::RawFormatError)?)?;
+
+        let data = fields.map(|b| u8::from_str_radix(b,16))
+                         .collect::<Result<Vec<u8>,_>>()?;
+        if data.len() != len {
+            return Err(NMEA2000Error::RawFormatError);
+        }
+
+        Ok(Raw{
+            date,
+            timestamp,
+            prio,
+            pgn,
+            src,
+            dest,
+            data
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::nmea::nmea2000::{From, Raw as _};
+
+    fn parse(line: &str) -> Result<Raw, NMEA2000Error>{
+        <Raw as From<String>>::from(&line.to_string())
+    }
+
+    #[test]
+    fn parse_line(){
+        let raw = parse("2021-06-16-17:33:21.141,2,129025,21,255,8,a0,7d,e6,18,c0,05,fb,d5").unwrap();
+        assert_eq!(raw.pgn(), 129025);
+        assert_eq!((raw.src(), raw.dest(), raw.prio()), (21, 255, 2));
+        assert_eq!(raw.date(), Some(18794));
+        assert_eq!((raw.timestamp().0, raw.timestamp().1), (17, 33));
+        assert_eq!(raw.data(), vec![0xA0, 0x7D, 0xE6, 0x18, 0xC0, 0x05, 0xFB, 0xD5]);
+    }
+
+    #[test]
+    fn iso_time(){
+        let raw = parse("2021-06-16T17:33:21.500Z,2,129025,21,255,8,a0,7d,e6,18,c0,05,fb,d5").unwrap();
+        assert_eq!(raw.timestamp(), (17, 33, 21.5));
+    }
+
+    #[test]
+    fn malformed_line(){
+        assert!(parse("2021-06-16-17:33:21.141,2,129025,21,255,8,a0,7d,e6,18,c0,05,fb").is_err());
+        assert!(parse("2021-13-16-17:33:21.141,2,129025,21,255,8,a0,7d,e6,18,c0,05,fb,d5").is_err());
+        assert!(parse("17:33:21.141,2,129025,21,255,8,a0,7d,e6,18,c0,05,fb,d5").is_err());
+        assert!(parse("2021-06-16-17:33:21.141,2,129025").is_err());
+    }
+}
@@ -0,0 +1,204 @@
+//! Tools to read and write Linux `candump -l` log files. Implements the `N2kRaw` trait.
+//! 
+//! candump log format:
+//! 
+//!  `(ssssssssss.uuuuuu) iface msgid#b0b1b2b3b4b5b6b7`
+//! 
+//!  where:
+//! 
+//!  • ssssssssss.uuuuuu — time of reception in seconds since January 1 1970 with microseconds
+//! 
+//!  • iface — name of the CAN interface, e.g., `can0`
+//! 
+//!  • msgid — 29-bit message identifier in hexadecimal format (contains NMEA 2000 PGN and other fields)
+//! 
+//!  • b0..b7 — message data bytes (from 0 to 8) in hexadecimal format without separators
+use crate::nmea::types::{TData, TDest, TPgn, TPrio, TSrc, Timestamp};
+use crate::nmea::nmea2000;
+use crate::nmea::nmea2000::NMEA2000Error;
+use crate::nmea::{days_from_secs, timestamp_from_secs};
+
+use std::fmt;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Interface name used when writing candump files
+pub const INTERFACE: &str = "can0";
+
+/// Maximal length of a fast packet message
+const FAST_PACKET_MAX_BYTES: usize = 223;
+
+/// Holds a candump message.
+/// 
+/// The values for priority, pgn, src and dest are derived.
+pub struct Raw{
+    //Parsed values
+    /// Days since January 1 1970
+    pub date : u16,
+    pub timestamp : Timestamp,
+    pub msgid : u32,
+    pub len : u8,
+    pub data : [u8;8],
+
+    //Derived values (ISO11783 Bits)
+    pub prio : u8,
+    pub pgn : u32,
+    pub src : u8,
+    pub dest : u8
+}
+
+impl nmea2000::Raw for Raw{
+    #[inline(always)]
+    fn timestamp(&self) -> Timestamp { self.timestamp }
+    #[inline(always)]
+    fn src(&self) -> TSrc { self.src }
+    #[inline(always)]
+    fn dest(&self) -> TDest { self.dest }
+    #[inline(always)]
+    fn prio(&self) -> TPrio { self.prio }
+    #[inline(always)]
+    fn pgn(&self) -> TPgn { self.pgn }
+    #[inline(always)]
+    fn data(&self) -> TData { self.data[..self.len as usize].to_vec() }
+    #[inline(always)]
+    fn date(&self) -> Option<u16> { Some(self.date) }
+
+    fn write(&self, m: &mut Box<dyn nmea2000::Message>) -> Result<(),NMEA2000Error>{
+        nmea2000::write_frame(self, m)
+    }
+}
+
+impl nmea2000::From<String> for Raw{
+    fn from(s: &String) -> Result<Self, NMEA2000Error>{
+        // Split data fields
+        let mut fields = s.split_whitespace();
+
+        //Parse time
+        let t = fields.next().ok_or(NMEA2000Error::RawFormatError)?;
+        let t = t.strip_prefix('(')
+                 .and_then(|t| t.strip_suffix(')'))
+                 .ok_or(NMEA2000Error::RawFormatError)?;
+        let secs = f64::from_str(t)?;
+        let date = days_from_secs(secs);
+        let timestamp = timestamp_from_secs(secs);
+
+        //Skip interface
+        fields.next().ok_or(NMEA2000Error::RawFormatError)?;
+
+        //Parse message id and data
+        let frame = fields.next().ok_or(NMEA2000Error::RawFormatError)?;
+        let (m, d) = frame.split_once('#').ok_or(NMEA2000Error::RawFormatError)?;
+        //Only extended frames carry NMEA 2000 messages
+        if m.len() != 8 || d.len() % 2 != 0 || d.len() > 16 || !d.is_ascii() {
+            return Err(NMEA2000Error::RawFormatError);
+        }
+        let msgid = u32::from_str_radix(m,16)?;
+        let (prio, pgn, src, dest) = nmea2000::decode_msgid(msgid);
+
+        let mut data = [0xFF;8];
+        for i in 0..d.len()/2{
+            data[i] = u8::from_str_radix(&d[2*i..2*i+2],16)?;
+        }
+
+        Ok(Raw{
+            date,
+            timestamp,
+            msgid,
+            len: (d.len()/2) as u8,
+            data,
+            prio,
+            pgn,
+            src,
+            dest
+        })
+    }
+}
+
+/// Display trait implementation, same format as a log line without timestamp and interface.
+impl fmt::Display for Raw{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        write!(f,"{:08X}#",self.msgid)?;
+        for b in &self.data[..self.len as usize]{
+            write!(f,"{:02X}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats `raw` as candump log line. Since [`Timestamp`] only holds the time of the day, the 
+/// date has to be supplied in `days` since January 1 1970.
+/// 
+/// Messages of more than 8 bytes, i.e., fast packets reassembled by the gateway, are split
+/// into several frames with one line each.
+pub fn to_line(raw: &dyn nmea2000::Raw, days: u16) -> Result<String, NMEA2000Error>{
+    let data = raw.data();
+    let (h, m, s) = raw.timestamp();
+    let secs = days as f64 * 86_400.0 + h as f64 * 3600.0 + m as f64 * 60.0 + s as f64;
+    let prefix = format!("({:.6}) {} {:08X}#", secs, INTERFACE,
+                         nmea2000::encode_msgid(raw.prio(), raw.pgn(), raw.src(), raw.dest()));
+
+    let mut frames = Vec::new();
+    if data.len() <= 8 {
+        frames.push(data);
+    } else if data.len() <= FAST_PACKET_MAX_BYTES {
+        //First frame holds the frame counter, the length and 6 data bytes, 
+        //all following the frame counter and 7 data bytes
+        let mut frame = vec![0, data.len() as u8];
+        frame.extend_from_slice(&data[..6]);
+        frames.push(frame);
+        for (i, chunk) in data[6..].chunks(7).enumerate(){
+            let mut frame = vec![(i + 1) as u8];
+            frame.extend_from_slice(chunk);
+            frame.resize(8, 0xFF);
+            frames.push(frame);
+        }
+    } else {
+        return Err(NMEA2000Error::UnexpectedPacketLength);
+    }
+
+    let mut lines = String::new();
+    for frame in frames{
+        if !lines.is_empty(){
+            lines.push('\n');
+        }
+        lines.push_str(&prefix);
+        for b in frame{
+            write!(lines, "{:02X}", b).unwrap();
+        }
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::nmea::nmea2000::{From, Raw as _};
+
+    fn parse(line: &str) -> Result<Raw, NMEA2000Error>{
+        <Raw as From<String>>::from(&line.to_string())
+    }
+
+    #[test]
+    fn parse_line(){
+        let raw = parse("(1623864801.141000) can0 09F80115#A07DE618C005FBD5").unwrap();
+        assert_eq!(raw.pgn(), 129025);
+        assert_eq!(raw.src(), 0x15);
+        assert_eq!(raw.date(), Some(18794));
+        assert_eq!((raw.timestamp().0, raw.timestamp().1), (17, 33));
+        assert_eq!(raw.data(), vec![0xA0, 0x7D, 0xE6, 0x18, 0xC0, 0x05, 0xFB, 0xD5]);
+    }
+
+    #[test]
+    fn malformed_line(){
+        assert!(parse("(1623864801.141000) can0 1F8#A07DE618").is_err());
+        assert!(parse("1623864801.141000 can0 09F80115#A07DE618C005FBD5").is_err());
+        assert!(parse("(1623864801.141000) can0 09F80115#A07DE61").is_err());
+    }
+
+    #[test]
+    fn line_roundtrip(){
+        let line = "(1623864801.500000) can0 09F80115#A07DE618C005FBD5";
+        let raw = parse(line).unwrap();
+        assert_eq!(to_line(&raw, raw.date).unwrap(), line);
+    }
+}
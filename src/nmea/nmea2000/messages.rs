@@ -3,7 +3,7 @@ use crate::nmea::types::{TData, TDest, TPgn, TPrio, TSrc, Timestamp};
 use crate::nmea::nmea2000;
 
 use crate::nmea::Float::*;
-use crate::nmea::MessageValue;
+use crate::nmea::{MessageValue, HEADING_MAGNETIC, HEADING_TRUE, TEMPERATURE_OUTSIDE, TEMPERATURE_SEA};
 use crate::nmea::MessageValue::*;
 
 /// Creates a message type that implements the trait nmea2000::MessageData
@@ -88,36 +88,107 @@ macro_rules! message_type {
     }
 }
 
+/// Integer type of a message field.
+///
+/// The largest values of a field are reserved: the maximum marks "data not available", the
+/// value below "out of range" and the one below that is reserved.
+trait Field: Copy{
+    /// Size of the field in bytes
+    const SIZE: usize;
+    /// Reads the field from little endian bytes
+    fn from_le(bytes: &[u8]) -> Self;
+    /// Returns `false` if the value is one of the reserved values
+    fn is_available(self) -> bool;
+    fn to_f32(self) -> f32;
+}
+
+/// Implements [`Field`] for an integer type
+macro_rules! field_type {
+    ($t: ty) => {
+        impl Field for $t{
+            const SIZE: usize = std::mem::size_of::<$t>();
+            #[inline(always)]
+            fn from_le(bytes: &[u8]) -> Self {<$t>::from_le_bytes(bytes.try_into().unwrap())}
+            #[inline(always)]
+            fn is_available(self) -> bool {self < <$t>::MAX - 2}
+            #[inline(always)]
+            fn to_f32(self) -> f32 {self as f32}
+        }
+    }
+}
+
+field_type!(u8);
+field_type!(u16);
+field_type!(i16);
+field_type!(u32);
+field_type!(i32);
+field_type!(i64);
+
+/// Unsigned 24 bit field
+#[derive(Clone, Copy)]
+struct U24(u32);
+
+impl Field for U24{
+    const SIZE: usize = 3;
+    #[inline(always)]
+    fn from_le(bytes: &[u8]) -> Self {U24(u32::from_le_bytes([bytes[0],bytes[1],bytes[2],0]))}
+    #[inline(always)]
+    fn is_available(self) -> bool {self.0 < 0xFF_FFFD}
+    #[inline(always)]
+    fn to_f32(self) -> f32 {self.0 as f32}
+}
+
+/// Returns the field of type `T` starting at byte `i`, `None` if it is not available
+#[inline(always)]
+fn field<T: Field>(data: &TData, i: usize) -> Option<T>{
+    Some(T::from_le(&data[i..i+T::SIZE])).filter(|v| v.is_available())
+}
+
+/// Returns the field of type `T` starting at byte `i` multiplied with `resolution`, NaN if it
+/// is not available
+#[inline(always)]
+fn value<T: Field>(data: &TData, i: usize, resolution: f32) -> f32{
+    field::<T>(data, i).map_or(f32::NAN, |v| v.to_f32() * resolution)
+}
+
+/// Wind reference of a ground referenced wind direction to true north
+const WIND_TRUE_NORTH: u8 = 0;
+/// Wind reference of a ground referenced wind direction to magnetic north
+const WIND_MAGNETIC_NORTH: u8 = 1;
+/// Wind reference of the apparent wind
+const WIND_APPARENT: u8 = 2;
+/// Wind reference of the true wind relative to the bow, referenced to the ground
+const WIND_TRUE_GROUND: u8 = 3;
+/// Wind reference of the true wind relative to the bow, referenced to the water
+const WIND_TRUE_WATER: u8 = 4;
+
 message_type!(WindMessage, 130306, 8, false);
 impl nmea2000::Message for WindMessage{
+    ///Wind speed in m/s and angle in rad according to the wind reference
     fn values(&self) -> Vec<MessageValue>{
-        let aws = u16::from_le_bytes([self.data[1],self.data[2]]) as f32 * 0.01;
-        let awa = u16::from_le_bytes([self.data[3],self.data[4]]) as f32 * 0.0001;
-        vec![WindSpeed(F16(aws)), 
-             WindAngle(F16(awa)),
-             Timestamp(self.timestamp)]
+        let speed = F16(value::<u16>(&self.data, 1, 0.01));
+        let angle = F16(value::<u16>(&self.data, 3, 0.0001));
+        let mut values = match self.data[5] & 0x07{
+            WIND_TRUE_NORTH => vec![GroundWindSpeed(speed), WindDirection(HEADING_TRUE, angle)],
+            WIND_MAGNETIC_NORTH => vec![GroundWindSpeed(speed), WindDirection(HEADING_MAGNETIC, angle)],
+            WIND_APPARENT => vec![WindSpeed(speed), WindAngle(angle)],
+            WIND_TRUE_GROUND => vec![GroundWindSpeed(speed), GroundWindAngle(angle)],
+            WIND_TRUE_WATER => vec![TrueWindSpeed(speed), TrueWindAngle(angle)],
+            //Reserved or not available reference
+            _ => vec![],
+        };
+        values.push(Timestamp(self.timestamp));
+        values
     }
 }
 
 message_type!(PositionRapidUpdateMessage, 129025, 8, false);
 impl nmea2000::Message for PositionRapidUpdateMessage{
-    ///Latitude & longitude 
-    fn values(&self) -> Vec<MessageValue>{
-        let mut lat = i32::from_le_bytes([  
-            self.data[0],
-            self.data[1],
-            self.data[2],
-            self.data[3]]) as f32;
-        lat *= 0.0000001; 
-
-        let mut long = i32::from_le_bytes([
-            self.data[4],
-            self.data[5],
-            self.data[6],
-            self.data[7]]) as f32;
-        long *= 0.0000001;
-
-        vec![Latitude(F32(lat)), 
+    ///Latitude & longitude
+    fn values(&self) -> Vec<MessageValue>{
+        let lat = value::<i32>(&self.data, 0, 0.0000001);
+        let long = value::<i32>(&self.data, 4, 0.0000001);
+        vec![Latitude(F32(lat)),
              Longitude(F32(long)),
              Timestamp(self.timestamp)]
     }
@@ -125,49 +196,93 @@ impl nmea2000::Message for PositionRapidUpdateMessage{
 
 message_type!(GNSSPositionData, 129029, 43, true);
 impl nmea2000::Message for GNSSPositionData{
-    ///Days since January 1 1970, Latitude and longitude in degrees
-    fn values(&self) -> Vec<MessageValue>{
-        //Days since January 1 1970
-        let date = u16::from_le_bytes([self.data[1],self.data[2]]);
-        let mut time = u32::from_le_bytes([self.data[3],self.data[4],self.data[5],self.data[6]]) as f32;
-        time *= 0.0001;
-        //Latitude
-        let mut lat = i64::from_le_bytes([ 
-            self.data[7],
-            self.data[8],
-            self.data[9],
-            self.data[10],
-            self.data[11],
-            self.data[12],
-            self.data[13],
-            self.data[14]]) as f64;
-        lat *= 0.0000000000000001;
-        //Longitude
-        let mut long = i64::from_le_bytes([ 
-            self.data[15],
-            self.data[16],
-            self.data[17],
-            self.data[18],
-            self.data[19],
-            self.data[20],
-            self.data[21],
-            self.data[22]]) as f64;
-        long *= 0.0000000000000001;
-        vec![Date(date),
-             Time(time),
-             Latitude(F64(lat)), 
-             Longitude(F64(long)),
-             Timestamp(self.timestamp)]
-    }    
+    ///Days since January 1 1970, Latitude and longitude in degrees, fix type, satellites,
+    ///DOPs and geoidal separation in meters
+    fn values(&self) -> Vec<MessageValue>{
+        let mut values = Vec::new();
+        //Days since January 1 1970 and seconds since midnight
+        if let (Some(date), Some(time)) = (field::<u16>(&self.data, 1), field::<u32>(&self.data, 3)){
+            values.push(Date(date));
+            values.push(Time(time as f32 * 0.0001));
+        }
+        let lat = field::<i64>(&self.data, 7).map_or(f64::NAN, |v| v as f64 * 0.0000000000000001);
+        let long = field::<i64>(&self.data, 15).map_or(f64::NAN, |v| v as f64 * 0.0000000000000001);
+        values.push(Latitude(F64(lat)));
+        values.push(Longitude(F64(long)));
+        //The method is the upper nibble, 15 is not available and 14 an error
+        let method = self.data[31] >> 4;
+        if method < 0x0D{
+            values.push(GnssMethod(method));
+        }
+        if let Some(satellites) = field::<u8>(&self.data, 33){
+            values.push(Satellites(satellites));
+        }
+        values.push(Hdop(F16(value::<i16>(&self.data, 34, 0.01))));
+        values.push(Pdop(F16(value::<i16>(&self.data, 36, 0.01))));
+        values.push(GeoidalSeparation(F32(value::<i32>(&self.data, 38, 0.01))));
+        values.push(Timestamp(self.timestamp));
+        values
+    }
+}
+
+message_type!(GNSSDOPsMessage, 129539, 8, false);
+impl nmea2000::Message for GNSSDOPsMessage{
+    ///Horizontal, vertical and time dilution of precision, the latter is not used
+    fn values(&self) -> Vec<MessageValue>{
+        let hdop = value::<i16>(&self.data, 2, 0.01);
+        let vdop = value::<i16>(&self.data, 4, 0.01);
+        vec![Hdop(F16(hdop)),
+             Vdop(F16(vdop)),
+             Timestamp(self.timestamp)]
+    }
+}
+
+message_type!(GNSSSatsInViewMessage, 129540, 3, true);
+impl nmea2000::Message for GNSSSatsInViewMessage{
+    ///Number of satellites in view, the details of each satellite are not used
+    fn values(&self) -> Vec<MessageValue>{
+        let mut values = Vec::new();
+        if let Some(satellites) = field::<u8>(&self.data, 2){
+            values.push(SatellitesInView(satellites));
+        }
+        values.push(Timestamp(self.timestamp));
+        values
+    }
 }
 
 message_type!(VesselHeadingMessage, 127250, 8, false);
 impl nmea2000::Message for VesselHeadingMessage{
-    ///Heading value in rad
+    ///Heading value with its reference and variation in rad
     fn values(&self) -> Vec<MessageValue>{
-        let hdg = u16::from_le_bytes([self.data[1],self.data[2]]) as f32 * 0.0001;
-        vec![Heading(F16(hdg)),
-            Timestamp(self.timestamp)]
+        let mut hdg = value::<u16>(&self.data, 1, 0.0001);
+        let reference = self.data[7] & 0x03;
+        let mut values = Vec::new();
+        //The variation comes first to derive the heading in the other reference. It is optional
+        //in this message, i.e., a variation of another message is kept if not available
+        if let Some(variation) = field::<i16>(&self.data, 5){
+            values.push(Variation(F16(variation as f32 * 0.0001)));
+        }
+        //The sensor reading of a magnetic heading is corrected by the deviation, if available
+        if let (HEADING_MAGNETIC, Some(deviation)) = (reference, field::<i16>(&self.data, 3)){
+            hdg += deviation as f32 * 0.0001;
+        }
+        values.push(Heading(reference, F16(hdg)));
+        values.push(Timestamp(self.timestamp));
+        values
+    }
+}
+
+message_type!(MagneticVariationMessage, 127258, 8, false);
+impl nmea2000::Message for MagneticVariationMessage{
+    ///Magnetic variation in rad
+    fn values(&self) -> Vec<MessageValue>{
+        let mut values = Vec::new();
+        //A variation of another message is kept if not available
+        if let Some(variation) = field::<i16>(&self.data, 4){
+            values.push(Variation(F16(variation as f32 * 0.0001)));
+        }
+        values.push(Timestamp(self.timestamp));
+        values
     }
 }
 
@@ -175,9 +290,9 @@ message_type!(CogSogRapidUpdateMessage, 129026, 8, false);
 impl nmea2000::Message for CogSogRapidUpdateMessage{
     ///Course over ground in rad, speed over ground in m/s
     fn values(&self) -> Vec<MessageValue>{
-        let cog = u16::from_le_bytes([self.data[2],self.data[3]]) as f32 * 0.0001;
-        let sog = u16::from_le_bytes([self.data[4],self.data[5]]) as f32 * 0.01;
-        vec![CourseOverGround(F16(cog)), 
+        let cog = value::<u16>(&self.data, 2, 0.0001);
+        let sog = value::<u16>(&self.data, 4, 0.01);
+        vec![CourseOverGround(F16(cog)),
              SpeedOverGround(F16(sog)),
              Timestamp(self.timestamp)]
     }
@@ -187,7 +302,7 @@ message_type!(SpeedMessage, 128259, 8, false);
 impl nmea2000::Message for SpeedMessage{
     ///Speed through water in m/s
     fn values(&self) -> Vec<MessageValue>{
-        let stw = u16::from_le_bytes([self.data[1],self.data[2]]) as f32 * 0.01;
+        let stw = value::<u16>(&self.data, 1, 0.01);
         vec![SpeedThroughWater(F16(stw)),
              Timestamp(self.timestamp)]
     }
@@ -197,10 +312,7 @@ message_type!(RateOfTurnMessage, 127251, 5, false);
 impl nmea2000::Message for RateOfTurnMessage{
     ///Rate of turn in radians/s
     fn values(&self) -> Vec<MessageValue>{
-        let rot = i32::from_le_bytes([self.data[1],
-                                      self.data[2],
-                                      self.data[3],
-                                      self.data[4]]) as f32 * 3.125e-08;
+        let rot = value::<i32>(&self.data, 1, 3.125e-08);
         vec![RateOfTurn(F32(rot)),
              Timestamp(self.timestamp)]
     }
@@ -210,9 +322,9 @@ message_type!(AttitudeMessage, 127257, 7, false);
 impl nmea2000::Message for AttitudeMessage{
     ///Yaw, pitch & roll in radians
     fn values(&self) -> Vec<MessageValue>{
-        let yaw = i16::from_le_bytes([self.data[1],self.data[2]]) as f32 * 0.0001;
-        let pitch = i16::from_le_bytes([self.data[3],self.data[4]]) as f32 * 0.0001;
-        let roll = i16::from_le_bytes([self.data[5],self.data[6]]) as f32 * 0.0001;
+        let yaw = value::<i16>(&self.data, 1, 0.0001);
+        let pitch = value::<i16>(&self.data, 3, 0.0001);
+        let roll = value::<i16>(&self.data, 5, 0.0001);
         vec![Yaw(F16(yaw)),
              Pitch(F16(pitch)),
              Roll(F16(roll)),
@@ -224,21 +336,300 @@ message_type!(RudderMessage, 127245, 8, false);
 impl nmea2000::Message for RudderMessage{
     ///Rudder angle in radians
     fn values(&self) -> Vec<MessageValue>{
-        let ra = i16::from_le_bytes([self.data[4],self.data[5]]) as f32 * 0.0001;
+        let ra = value::<i16>(&self.data, 4, 0.0001);
         vec![RudderAngle(F16(ra)),
              Timestamp(self.timestamp)]
     }
 }
 
+message_type!(WaterDepthMessage, 128267, 8, false);
+impl nmea2000::Message for WaterDepthMessage{
+    ///Depth in meters, including the offset of the transducer to the waterline (positive)
+    ///or to the keel (negative)
+    fn values(&self) -> Vec<MessageValue>{
+        let depth = value::<u32>(&self.data, 1, 0.01);
+        //Without an offset the depth is below the transducer
+        let offset = field::<i16>(&self.data, 5).map_or(0.0, |v| v as f32 * 0.001);
+        vec![Depth(F32(depth + offset)),
+             Timestamp(self.timestamp)]
+    }
+}
+
+message_type!(OutsideEnvironmentalMessage, 130310, 7, false);
+impl nmea2000::Message for OutsideEnvironmentalMessage{
+    ///Water and outside air temperature in Kelvin, atmospheric pressure in Pascal
+    fn values(&self) -> Vec<MessageValue>{
+        let water = value::<u16>(&self.data, 1, 0.01);
+        let air = value::<u16>(&self.data, 3, 0.01);
+        let pressure = value::<u16>(&self.data, 5, 100.0);
+        vec![Temperature(TEMPERATURE_SEA, F16(water)),
+             Temperature(TEMPERATURE_OUTSIDE, F16(air)),
+             Pressure(F16(pressure)),
+             Timestamp(self.timestamp)]
+    }
+}
+
+message_type!(EnvironmentalMessage, 130311, 8, false);
+impl nmea2000::Message for EnvironmentalMessage{
+    ///Temperature in Kelvin, relative humidity in percent and atmospheric pressure in Pascal
+    fn values(&self) -> Vec<MessageValue>{
+        let temperature_source = self.data[1] & 0x3F;
+        let humidity_source = self.data[1] >> 6;
+        let temperature = value::<u16>(&self.data, 2, 0.01);
+        let humidity = value::<i16>(&self.data, 4, 0.004);
+        let pressure = value::<u16>(&self.data, 6, 100.0);
+        vec![Temperature(temperature_source, F16(temperature)),
+             Humidity(humidity_source, F16(humidity)),
+             Pressure(F16(pressure)),
+             Timestamp(self.timestamp)]
+    }
+}
+
+message_type!(TemperatureMessage, 130312, 7, false);
+impl nmea2000::Message for TemperatureMessage{
+    ///Temperature in Kelvin
+    fn values(&self) -> Vec<MessageValue>{
+        let temperature = value::<u16>(&self.data, 3, 0.01);
+        vec![Temperature(self.data[2], F16(temperature)),
+             Timestamp(self.timestamp)]
+    }
+}
+
+message_type!(TemperatureExtendedMessage, 130316, 8, false);
+impl nmea2000::Message for TemperatureExtendedMessage{
+    ///Temperature in Kelvin
+    fn values(&self) -> Vec<MessageValue>{
+        let temperature = value::<U24>(&self.data, 3, 0.001);
+        vec![Temperature(self.data[2], F32(temperature)),
+             Timestamp(self.timestamp)]
+    }
+}
+
+message_type!(EngineRapidMessage, 127488, 8, false);
+impl nmea2000::Message for EngineRapidMessage{
+    ///Engine speed in revolutions per minute
+    fn values(&self) -> Vec<MessageValue>{
+        let rpm = value::<u16>(&self.data, 1, 0.25);
+        vec![EngineSpeed(self.data[0], F16(rpm)),
+             Timestamp(self.timestamp)]
+    }
+}
+
+message_type!(EngineDynamicMessage, 127489, 26, true);
+impl nmea2000::Message for EngineDynamicMessage{
+    ///Coolant temperature in Kelvin, fuel rate in liters per hour
+    fn values(&self) -> Vec<MessageValue>{
+        let coolant = value::<u16>(&self.data, 5, 0.01);
+        let fuel_rate = value::<i16>(&self.data, 9, 0.1);
+        vec![CoolantTemperature(self.data[0], F16(coolant)),
+             FuelRate(self.data[0], F16(fuel_rate)),
+             Timestamp(self.timestamp)]
+    }
+}
+
+message_type!(FluidLevelMessage, 127505, 8, false);
+impl nmea2000::Message for FluidLevelMessage{
+    ///Fluid type, tank instance and level in percent
+    fn values(&self) -> Vec<MessageValue>{
+        let instance = self.data[0] & 0x0F;
+        let fluid = self.data[0] >> 4;
+        let level = value::<i16>(&self.data, 1, 0.004);
+        vec![FluidLevel(fluid, instance, F16(level)),
+             Timestamp(self.timestamp)]
+    }
+}
+
+message_type!(BatteryStatusMessage, 127508, 8, false);
+impl nmea2000::Message for BatteryStatusMessage{
+    ///Voltage in Volt, current in Ampere and temperature in Kelvin
+    fn values(&self) -> Vec<MessageValue>{
+        let voltage = value::<i16>(&self.data, 1, 0.01);
+        let current = value::<i16>(&self.data, 3, 0.1);
+        let temperature = value::<u16>(&self.data, 5, 0.01);
+        vec![BatteryVoltage(self.data[0], F16(voltage)),
+             BatteryCurrent(self.data[0], F16(current)),
+             BatteryTemperature(self.data[0], F16(temperature)),
+             Timestamp(self.timestamp)]
+    }
+}
+
+message_type!(DCDetailedStatusMessage, 127506, 11, true);
+impl nmea2000::Message for DCDetailedStatusMessage{
+    ///State of charge in percent
+    fn values(&self) -> Vec<MessageValue>{
+        let soc = value::<u8>(&self.data, 3, 1.0);
+        vec![StateOfCharge(self.data[1], F16(soc)),
+             Timestamp(self.timestamp)]
+    }
+}
+
+message_type!(DistanceLogMessage, 128275, 14, true);
+impl nmea2000::Message for DistanceLogMessage{
+    ///Total and trip log in meters
+    fn values(&self) -> Vec<MessageValue>{
+        let log = value::<u32>(&self.data, 6, 1.0);
+        let trip = value::<u32>(&self.data, 10, 1.0);
+        vec![Log(F32(log)),
+             TripLog(F32(trip)),
+             Timestamp(self.timestamp)]
+    }
+}
+
+message_type!(CrossTrackErrorMessage, 129283, 8, false);
+impl nmea2000::Message for CrossTrackErrorMessage{
+    ///Cross track error in meters
+    fn values(&self) -> Vec<MessageValue>{
+        let xte = value::<i32>(&self.data, 2, 0.01);
+        vec![CrossTrackError(F32(xte)),
+             Timestamp(self.timestamp)]
+    }
+}
+
+message_type!(NavigationDataMessage, 129284, 34, true);
+impl nmea2000::Message for NavigationDataMessage{
+    ///Distance in meters and bearing in rad to the destination waypoint, closing velocity in m/s
+    fn values(&self) -> Vec<MessageValue>{
+        let dtw = value::<u32>(&self.data, 1, 0.01);
+        let reference = self.data[5] & 0x03;
+        let btw = value::<u16>(&self.data, 14, 0.0001);
+        let vmg = value::<i16>(&self.data, 32, 0.01);
+        let mut values = vec![DistanceToWaypoint(F32(dtw)),
+                              BearingToWaypoint(reference, F16(btw)),
+                              WaypointClosingVelocity(F16(vmg))];
+        if let Some(destination) = field::<u32>(&self.data, 20){
+            values.push(DestinationWaypoint(destination));
+        }
+        values.push(Timestamp(self.timestamp));
+        values
+    }
+}
+
+/// Decodes a variable length string starting at `i`, i.e., length including the two header
+/// bytes, encoding and characters. Returns the string and the index after it.
+fn string_lau(data: &[u8], i: usize) -> Option<(String, usize)>{
+    let len = *data.get(i)? as usize;
+    let text = match data.get(i+1)?{
+        //ASCII
+        1 => String::from_utf8_lossy(data.get(i+2..i+len)?).into_owned(),
+        //UTF-16
+        _ => String::from_utf16_lossy(&data.get(i+2..i+len)?
+                .chunks_exact(2).map(|c| u16::from_le_bytes([c[0],c[1]])).collect::<Vec<_>>()),
+    };
+    Some((text, i + len.max(2)))
+}
+
+message_type!(RouteInformationMessage, 129285, 12, true);
+impl nmea2000::Message for RouteInformationMessage{
+    ///Number and name of the waypoints of the route
+    fn values(&self) -> Vec<MessageValue>{
+        let items = field::<u16>(&self.data, 2).unwrap_or(0);
+        let mut values = Vec::new();
+        //Route name and a reserved byte precede the waypoints
+        let mut i = match string_lau(&self.data, 9){
+            Some((_, i)) => i + 1,
+            None => return vec![Timestamp(self.timestamp)],
+        };
+        for _ in 0..items{
+            let id = match self.data.get(i..i+2){
+                Some(id) => u16::from_le_bytes([id[0],id[1]]) as u32,
+                None => break,
+            };
+            let (name, next) = match string_lau(&self.data, i+2){
+                Some(s) => s,
+                None => break,
+            };
+            values.push(Waypoint(id, name));
+            //Latitude and longitude follow the name
+            i = next + 8;
+        }
+        values.push(Timestamp(self.timestamp));
+        values
+    }
+}
+
 message_type!(TimeDateMessage, 129033, 8, false);
 impl nmea2000::Message for TimeDateMessage{
     fn values(&self) -> Vec<MessageValue>{
-        let date = u16::from_le_bytes([self.data[0],self.data[1]]);
-        let time = u32::from_le_bytes([self.data[2],self.data[3],self.data[4],self.data[5]]) as f32 * 0.0001;
-        let offset = i16::from_le_bytes([self.data[6],self.data[7]]);
-        vec![Date(date),
-             Time(time),
-             LocalOffset(offset),
-             Timestamp(self.timestamp)]
+        let mut values = Vec::new();
+        if let (Some(date), Some(time)) = (field::<u16>(&self.data, 0), field::<u32>(&self.data, 2)){
+            //Without a local offset the time is UTC
+            let offset = field::<i16>(&self.data, 6).unwrap_or(0);
+            values.push(Date(date));
+            values.push(Time(time as f32 * 0.0001));
+            values.push(LocalOffset(offset));
+        }
+        values.push(Timestamp(self.timestamp));
+        values
+    }
+}
+
+/// Decodes a text field of an AIS message, which is padded with `@`, spaces or `0xFF`
+fn ais_text(data: &[u8]) -> String{
+    String::from_utf8_lossy(data)
+        .trim_end_matches(['@', ' ', '\0', char::REPLACEMENT_CHARACTER])
+        .to_string()
+}
+
+/// Values of the AIS Class A and Class B position reports, which share the layout up to the SOG.
+/// Reports without a position, i.e., not available or outside of the valid range, are skipped.
+fn ais_position_values(data: &TData, timestamp: Timestamp) -> Vec<MessageValue>{
+    let mmsi = u32::from_le_bytes([data[1],data[2],data[3],data[4]]);
+    let long = value::<i32>(data, 5, 0.0000001);
+    let lat = value::<i32>(data, 9, 0.0000001);
+    let cog = value::<u16>(data, 14, 0.0001);
+    let sog = value::<u16>(data, 16, 0.01);
+    //The time of the report is the time the target was last seen, i.e., it comes first
+    let mut values = vec![Timestamp(timestamp)];
+    //AIS marks a position that is not available with 181° longitude and 91° latitude
+    if long.abs() <= 180.0 && lat.abs() <= 90.0{
+        values.push(AisPosition(mmsi, F32(lat), F32(long), F16(cog), F16(sog)));
+    }
+    values
+}
+
+message_type!(AisClassAPositionMessage, 129038, 27, true);
+impl nmea2000::Message for AisClassAPositionMessage{
+    ///MMSI, latitude & longitude in degrees, COG in rad and SOG in m/s
+    fn values(&self) -> Vec<MessageValue>{
+        ais_position_values(&self.data, self.timestamp)
     }
-}
\ No newline at end of file
+}
+
+message_type!(AisClassBPositionMessage, 129039, 26, true);
+impl nmea2000::Message for AisClassBPositionMessage{
+    ///MMSI, latitude & longitude in degrees, COG in rad and SOG in m/s
+    fn values(&self) -> Vec<MessageValue>{
+        ais_position_values(&self.data, self.timestamp)
+    }
+}
+
+message_type!(AisClassAStaticMessage, 129794, 75, true);
+impl nmea2000::Message for AisClassAStaticMessage{
+    ///MMSI, call sign and name of the vessel
+    fn values(&self) -> Vec<MessageValue>{
+        let mmsi = u32::from_le_bytes([self.data[1],self.data[2],self.data[3],self.data[4]]);
+        vec![Timestamp(self.timestamp),
+             AisCallsign(mmsi, ais_text(&self.data[9..16])),
+             AisName(mmsi, ais_text(&self.data[16..36]))]
+    }
+}
+
+message_type!(AisClassBStaticPartAMessage, 129809, 25, true);
+impl nmea2000::Message for AisClassBStaticPartAMessage{
+    ///MMSI and name of the vessel
+    fn values(&self) -> Vec<MessageValue>{
+        let mmsi = u32::from_le_bytes([self.data[1],self.data[2],self.data[3],self.data[4]]);
+        vec![Timestamp(self.timestamp),
+             AisName(mmsi, ais_text(&self.data[5..25]))]
+    }
+}
+
+message_type!(AisClassBStaticPartBMessage, 129810, 34, true);
+impl nmea2000::Message for AisClassBStaticPartBMessage{
+    ///MMSI and call sign of the vessel
+    fn values(&self) -> Vec<MessageValue>{
+        let mmsi = u32::from_le_bytes([self.data[1],self.data[2],self.data[3],self.data[4]]);
+        vec![Timestamp(self.timestamp),
+             AisCallsign(mmsi, ais_text(&self.data[13..20]))]
+    }
+}
@@ -1,14 +1,22 @@
 use crate::nmea::types::{TData, TDest, TPgn, TPrio, TSrc, Timestamp};
 use crate::nmea::nmea2000::messages::*;
-use crate::nmea::MessageValue;
+use crate::nmea::{MessageValue, Parse};
 
+use std::cmp;
 use std::collections::HashMap;
 use std::marker;
 
 use thiserror::Error;
 
+pub mod actisense;
+pub mod candump;
+pub mod canboat;
 pub mod messages;
+pub mod n2kascii;
+pub mod pcdin;
+pub mod socketcan;
 pub mod yd;
+pub mod ydvr;
 
 /// NMEA2000 Raw format
 /// 
@@ -21,6 +29,8 @@ pub trait Raw{
     fn pgn(&self) -> TPgn;
     fn data(&self) -> TData;
     fn write(&self, message: &mut Box<dyn Message>) -> Result<(),NMEA2000Error>;
+    /// Days since January 1 1970 (UTC) for formats that hold the date of every frame
+    fn date(&self) -> Option<u16> { None }
 }
 
 /// Read a `Raw` packet from some type `T`
@@ -37,6 +47,7 @@ pub trait Message: MessageData+Send{
 }
 
 /// Functions to get access to [`Message`] fields
+#[allow(dead_code)]
 pub trait MessageData{
     fn timestamp(&self) -> Timestamp;
     fn timestamp_mut(&mut self) -> &mut Timestamp;
@@ -81,6 +92,8 @@ pub trait MessageData{
 pub struct Parser<T,U>{
     /// Messages are stored here if they are not completely received.
     messages: HashMap<(TSrc, TPgn), Box<dyn Message>>,
+    /// Date of the latest [`Raw`], see [`Raw::date`]
+    date: Option<u16>,
     _raw_type: marker::PhantomData<T>,
     _ingest_type: marker::PhantomData<U>
 }
@@ -99,6 +112,7 @@ impl<T: Raw + From<U>,U> Parser<T,U>{
     pub fn new() -> Self{ 
         Parser::<T,U>{
                     messages: HashMap::new(), 
+                    date: None,
                     _raw_type: marker::PhantomData, 
                     _ingest_type: marker::PhantomData
                 } 
@@ -121,11 +135,16 @@ impl<T: Raw + From<U>,U> Parser<T,U>{
     /// }
     /// ```
     pub fn parse(&mut self, src: &U) -> Result<Option<Box<dyn Message>>,NMEA2000Error>{
-        let raw = T::from(src)?;
+        let raw = match T::from(src){
+            //Corrupted on the way, just drop it
+            Err(NMEA2000Error::ChecksumError) => return Ok(None),
+            r => r?
+        };
         self.parse_from_raw(&raw)
     }
 
     pub fn parse_from_raw(&mut self, raw: &T) -> Result<Option<Box<dyn Message>>,NMEA2000Error>{
+        self.date = raw.date();
         let mut message : Box<dyn Message>;
         if let Some(m) = self.messages.remove(&(raw.src(),raw.pgn())){
             message = m;
@@ -134,12 +153,34 @@ impl<T: Raw + From<U>,U> Parser<T,U>{
                 WindMessage::PGN                    => Box::new(WindMessage::new()),
                 PositionRapidUpdateMessage::PGN     => Box::new(PositionRapidUpdateMessage::new()),
                 GNSSPositionData::PGN               => Box::new(GNSSPositionData::new()),
+                GNSSDOPsMessage::PGN                => Box::new(GNSSDOPsMessage::new()),
+                GNSSSatsInViewMessage::PGN          => Box::new(GNSSSatsInViewMessage::new()),
                 VesselHeadingMessage::PGN           => Box::new(VesselHeadingMessage::new()),
+                MagneticVariationMessage::PGN       => Box::new(MagneticVariationMessage::new()),
                 CogSogRapidUpdateMessage::PGN       => Box::new(CogSogRapidUpdateMessage::new()),
                 SpeedMessage::PGN                   => Box::new(SpeedMessage::new()),
                 RateOfTurnMessage::PGN              => Box::new(RateOfTurnMessage::new()),
                 AttitudeMessage::PGN                => Box::new(AttitudeMessage::new()),
                 RudderMessage::PGN                  => Box::new(RudderMessage::new()),
+                WaterDepthMessage::PGN              => Box::new(WaterDepthMessage::new()),
+                OutsideEnvironmentalMessage::PGN    => Box::new(OutsideEnvironmentalMessage::new()),
+                EnvironmentalMessage::PGN           => Box::new(EnvironmentalMessage::new()),
+                TemperatureMessage::PGN             => Box::new(TemperatureMessage::new()),
+                TemperatureExtendedMessage::PGN     => Box::new(TemperatureExtendedMessage::new()),
+                EngineRapidMessage::PGN             => Box::new(EngineRapidMessage::new()),
+                EngineDynamicMessage::PGN           => Box::new(EngineDynamicMessage::new()),
+                FluidLevelMessage::PGN              => Box::new(FluidLevelMessage::new()),
+                BatteryStatusMessage::PGN           => Box::new(BatteryStatusMessage::new()),
+                DCDetailedStatusMessage::PGN        => Box::new(DCDetailedStatusMessage::new()),
+                CrossTrackErrorMessage::PGN         => Box::new(CrossTrackErrorMessage::new()),
+                NavigationDataMessage::PGN          => Box::new(NavigationDataMessage::new()),
+                RouteInformationMessage::PGN        => Box::new(RouteInformationMessage::new()),
+                DistanceLogMessage::PGN             => Box::new(DistanceLogMessage::new()),
+                AisClassAPositionMessage::PGN       => Box::new(AisClassAPositionMessage::new()),
+                AisClassBPositionMessage::PGN       => Box::new(AisClassBPositionMessage::new()),
+                AisClassAStaticMessage::PGN         => Box::new(AisClassAStaticMessage::new()),
+                AisClassBStaticPartAMessage::PGN    => Box::new(AisClassBStaticPartAMessage::new()),
+                AisClassBStaticPartBMessage::PGN    => Box::new(AisClassBStaticPartBMessage::new()),
                 TimeDateMessage::PGN                => Box::new(TimeDateMessage::new()),
                 _ => return Ok(None)
             }
@@ -162,6 +203,145 @@ impl<T: Raw + From<U>,U> Parser<T,U>{
     }
 }
 
+/// Derives priority, PGN, source and destination from a 29-bit CAN message identifier.
+/// 
+/// Without the help of the canboat project (https://github.com/canboat/canboat/) it would
+/// have been a lot harder to find out how this works.
+pub fn decode_msgid(msgid: u32) -> (TPrio, TPgn, TSrc, TDest){
+    //ISO11783 Bits
+    let pf : u8 = (msgid >> 16) as u8;
+    let ps : u8 = (msgid >> 8) as u8;
+    let rdp : u8 = ((msgid >> 24) & 3) as u8;
+
+    let src = msgid as u8;
+    let prio = ((msgid >> 26) & 0x7) as u8;
+    
+    let (dest,pgn) : (u8,u32);
+    if pf < 240{
+        dest = ps;
+        pgn = ((rdp as u32) << 16) + ((pf as u32) << 8);
+    }else{
+        dest = 0xff;
+        pgn = ((rdp as u32) << 16) + ((pf as u32) << 8) + (ps as u32);
+    }
+    (prio, pgn, src, dest)
+}
+
+/// Builds a 29-bit CAN message identifier from priority, PGN, source and destination.
+/// Inverse of [`decode_msgid`].
+pub fn encode_msgid(prio: TPrio, pgn: TPgn, src: TSrc, dest: TDest) -> u32{
+    let pf = (pgn >> 8) as u8;
+    let ps = if pf < 240 { dest } else { pgn as u8 };
+    (((prio & 0x7) as u32) << 26)
+        + (((pgn >> 16) & 3) << 24)
+        + ((pf as u32) << 16)
+        + ((ps as u32) << 8)
+        + (src as u32)
+}
+
+/// Writes a single CAN frame of up to 8 bytes into `m`. Takes care of the reassembly of 
+/// fast packets, i.e., messages that are spread over several frames.
+/// 
+/// Frames shorter than 8 bytes are padded with `0xFF`. Fast packets may be longer than the
+/// bytes of the message type, e.g., AIS messages of newer devices, the additional bytes are kept.
+pub fn write_frame(raw: &dyn Raw, m: &mut Box<dyn Message>) -> Result<(),NMEA2000Error>{
+    let mut data = raw.data();
+    data.resize(8, 0xFF);
+
+    //Is this a fast message?
+    //(This part is optimized in the compiler and only present
+    // in messages which are consisting of several raw-packets)
+    if m.is_fast(){
+        //If we are just starting this new fast package
+        if (m.next_packet() == 0) && (data[0] & 0x1F == 0){
+            //Check if this packet has the same length as we expect to see
+            if (data[1] as usize) < m.bytes() {
+                return Err(NMEA2000Error::UnexpectedPacketLength);
+            }
+            //Set values and the first 6 bytes for this package
+            *m.timestamp_mut() = raw.timestamp();
+            *m.src_mut() = raw.src();
+            *m.dest_mut() = raw.dest();
+            *m.prio_mut() = raw.prio();
+            *m.counter_mask_mut() = data[0];
+            *m.next_packet_mut() += 1;
+            *m.remaining_bytes_mut() = data[1] as usize - cmp::min(data[1] as usize,6);
+            m.data_mut().extend_from_slice(&data[2..8]);
+        } else {
+            //This packet is already begun...
+            //If the packet is the next in series
+            if m.next_packet() == (m.counter_mask() ^ data[0]){
+                let l = cmp::min(m.remaining_bytes()+1,8);
+                m.data_mut().extend_from_slice(&data[1..l]);
+                *m.remaining_bytes_mut() -= cmp::min(m.remaining_bytes(),7);
+                *m.next_packet_mut() += 1;
+            } else {
+                //It seems that the previous sequence was not finished. Try to start a new sequence.
+                //Check that only bits in sequence identifier (raw.data[0] & 0b00011111) and sequence
+                //size with what we expect.
+                if (data[0] & 0x1F == 0) && ((data[1] as usize) >= m.bytes()){
+                    *m.timestamp_mut() = raw.timestamp();
+                    *m.src_mut() = raw.src();
+                    *m.dest_mut() = raw.dest();
+                    *m.prio_mut() = raw.prio();
+                    *m.counter_mask_mut() = data[0];
+                    *m.next_packet_mut() = 1;
+                    *m.remaining_bytes_mut() = data[1] as usize - cmp::min(data[1] as usize,6);
+                    m.data_mut().clear();
+                    m.data_mut().extend_from_slice(&data[2..8]);
+                } else {
+                    return Err(NMEA2000Error::PacketOutOfSequence);
+                }
+            }
+        }
+    } else {
+        //Just a normal packet
+        *m.timestamp_mut() = raw.timestamp();
+        *m.src_mut() = raw.src();
+        *m.dest_mut() = raw.dest();
+        *m.prio_mut() = raw.prio();
+        m.data_mut().append(&mut data);
+    }
+    Ok(())
+}
+
+/// Writes a complete message into `m`, i.e., from formats in which fast packets are
+/// already reassembled by the gateway.
+pub fn write_message(raw: &dyn Raw, m: &mut Box<dyn Message>) -> Result<(),NMEA2000Error>{
+    let mut data = raw.data();
+    if data.len() < m.bytes() {
+        return Err(NMEA2000Error::UnexpectedPacketLength);
+    }
+    *m.timestamp_mut() = raw.timestamp();
+    *m.src_mut() = raw.src();
+    *m.dest_mut() = raw.dest();
+    *m.prio_mut() = raw.prio();
+    *m.remaining_bytes_mut() = 0;
+    m.data_mut().clear();
+    m.data_mut().append(&mut data);
+    Ok(())
+}
+
+impl<T: Raw + From<U>,U> Parse<U> for Parser<T,U>{
+    type Error = NMEA2000Error;
+
+    fn parse_values(&mut self, src: &U) -> Result<Option<Vec<MessageValue>>,NMEA2000Error>{
+        let message = match self.parse(src)?{
+            Some(message) => message,
+            None => return Ok(None),
+        };
+        let mut values = message.values();
+        //The date of the frames is used like a date message, i.e., no date messages are needed
+        if let Some(days) = self.date{
+            let (h, m, s) = message.timestamp();
+            values.push(MessageValue::Date(days));
+            values.push(MessageValue::Time(h as f32 * 3600.0 + m as f32 * 60.0 + s));
+            values.push(MessageValue::LocalOffset(0));
+        }
+        Ok(Some(values))
+    }
+}
+
 #[derive(Error,Debug)]
 pub enum NMEA2000Error{
     #[error("unknown raw format")]
@@ -174,4 +354,66 @@ pub enum NMEA2000Error{
     PacketOutOfSequence,
     #[error("unexpected length of packet")]
     UnexpectedPacketLength,
-}
\ No newline at end of file
+    #[error("checksum mismatch")]
+    ChecksumError,
+}
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn parse(parser: &mut Parser<yd::Raw,String>, line: &str) -> Option<Box<dyn Message>>{
+        parser.parse(&line.to_string()).unwrap()
+    }
+
+    #[test]
+    fn ais_static_data(){
+        //129809 of MMSI 211000001 with the name `WIND, "SEA"` padded with `@`
+        let mut parser = Parser::<yd::Raw,String>::new();
+        assert!(parse(&mut parser, "12:00:00.000 R 11FB1101 40 19 18 C1 9A 93 0C 57").is_none());
+        assert!(parse(&mut parser, "12:00:00.000 R 11FB1101 41 49 4E 44 2C 20 22 53").is_none());
+        assert!(parse(&mut parser, "12:00:00.000 R 11FB1101 42 45 41 22 40 40 40 40").is_none());
+        let m = parse(&mut parser, "12:00:00.000 R 11FB1101 43 40 40 40 40 40 FF FF").unwrap();
+        assert_eq!(m.pgn(), 129809);
+        assert!(matches!(&m.values()[1], MessageValue::AisName(211000001, name) if name == "WIND, \"SEA\""));
+    }
+
+    #[test]
+    fn satellites_in_view_in_single_frame(){
+        //129540 without satellites in view only has 3 bytes
+        let mut parser = Parser::<yd::Raw,String>::new();
+        let m = parse(&mut parser, "12:00:00.000 R 19FA0401 00 03 01 02 00 FF FF FF").unwrap();
+        assert_eq!(m.pgn(), 129540);
+        assert!(matches!(m.values()[0], MessageValue::SatellitesInView(0)));
+    }
+
+    #[test]
+    fn distance_log_over_several_frames(){
+        //128275 with a log of 1000 m and a trip log of 20 m
+        let mut parser = Parser::<yd::Raw,String>::new();
+        assert!(parse(&mut parser, "12:00:00.000 R 19F51323 40 0E 01 02 03 04 05 06").is_none());
+        assert!(parse(&mut parser, "12:00:00.010 R 19F51323 41 E8 03 00 00 14 00 00").is_none());
+        let m = parse(&mut parser, "12:00:00.020 R 19F51323 42 00 FF FF FF FF FF FF").unwrap();
+        assert_eq!(m.data(), &vec![1, 2, 3, 4, 5, 6, 0xE8, 3, 0, 0, 0x14, 0, 0, 0]);
+        assert!(matches!(m.values()[0], MessageValue::Log(crate::nmea::Float::F32(l)) if l == 1000.0));
+        assert!(matches!(m.values()[1], MessageValue::TripLog(crate::nmea::Float::F32(l)) if l == 20.0));
+    }
+
+    #[test]
+    fn distance_log_restarts_after_lost_frame(){
+        let mut parser = Parser::<yd::Raw,String>::new();
+        assert!(parse(&mut parser, "12:00:00.000 R 19F51323 40 0E 01 02 03 04 05 06").is_none());
+        //Second frame of the sequence is lost, a new sequence starts
+        assert!(parse(&mut parser, "12:00:01.000 R 19F51323 60 0E 06 05 04 03 02 01").is_none());
+        assert!(parse(&mut parser, "12:00:01.010 R 19F51323 61 00 00 00 00 00 00 00").is_none());
+        let m = parse(&mut parser, "12:00:01.020 R 19F51323 62 00 FF FF FF FF FF FF").unwrap();
+        assert_eq!(&m.data()[..6], &[6, 5, 4, 3, 2, 1]);
+        assert_eq!(m.data().len(), 14);
+    }
+
+    #[test]
+    fn distance_log_with_unexpected_length(){
+        let mut parser = Parser::<yd::Raw,String>::new();
+        assert!(parse(&mut parser, "12:00:00.000 R 19F51323 40 08 01 02 03 04 05 06").is_none());
+        assert!(parse(&mut parser, "12:00:00.010 R 19F51323 41 E8 03 00 00 14 00 00").is_none());
+    }
+}
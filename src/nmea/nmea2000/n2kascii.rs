@@ -0,0 +1,134 @@
+//! Tools to read the Actisense N2K ASCII format, e.g., of the Actisense W2K-1 gateway. Implements
+//! the `N2kRaw` trait.
+//! 
+//! Actisense N2K ASCII format:
+//! 
+//!  `Ahhmmss.ddd SSDDP PPPPP b0b1b2...<CR><LF>`
+//! 
+//!  where:
+//! 
+//!  • hhmmss.ddd — time of reception. Some devices write the seconds since they were started
+//!    instead, which is assumed if the value is not a valid time of the day
+//! 
+//!  • SSDDP — source, destination and priority in hexadecimal format
+//! 
+//!  • PPPPP — PGN in hexadecimal format
+//! 
+//!  • b0.. — data bytes of the complete message in hexadecimal format without separators
+use crate::nmea::types::{TData, TDest, TPgn, TPrio, TSrc, Timestamp};
+use crate::nmea::{nmea2000, timestamp_from_secs};
+use crate::nmea::nmea2000::NMEA2000Error;
+
+use std::str::FromStr;
+
+/// Holds an Actisense N2K ASCII message.
+pub struct Raw{
+    pub timestamp : Timestamp,
+    pub prio : u8,
+    pub pgn : u32,
+    pub src : u8,
+    pub dest : u8,
+    pub data : Vec<u8>
+}
+
+impl nmea2000::Raw for Raw{
+    #[inline(always)]
+    fn timestamp(&self) -> Timestamp { self.timestamp }
+    #[inline(always)]
+    fn src(&self) -> TSrc { self.src }
+    #[inline(always)]
+    fn dest(&self) -> TDest { self.dest }
+    #[inline(always)]
+    fn prio(&self) -> TPrio { self.prio }
+    #[inline(always)]
+    fn pgn(&self) -> TPgn { self.pgn }
+    #[inline(always)]
+    fn data(&self) -> TData { self.data.clone() }
+
+    fn write(&self, m: &mut Box<dyn nmea2000::Message>) -> Result<(),NMEA2000Error>{
+        if m.is_fast() {
+            nmea2000::write_message(self, m)
+        } else {
+            nmea2000::write_frame(self, m)
+        }
+    }
+}
+
+impl nmea2000::From<String> for Raw{
+    fn from(s: &String) -> Result<Self, NMEA2000Error>{
+        // Split data fields
+        let mut fields = s.split_whitespace();
+
+        //Parse time
+        let t = fields.next()
+                      .and_then(|t| t.strip_prefix('A'))
+                      .ok_or(NMEA2000Error::RawFormatError)?;
+        let secs = f64::from_str(t)?;
+        let (h, m, s) = ((secs / 10_000.0) as u8, (secs / 100.0 % 100.0) as u8, secs % 100.0);
+        let timestamp = if secs < 240_000.0 && m < 60 && s < 60.0 {
+            (h, m, s as f32)
+        } else {
+            timestamp_from_secs(secs)
+        };
+
+        //Parse source, destination and priority
+        let sdp = u32::from_str_radix(fields.next().ok_or(NMEA2000Error::RawFormatError)?, 16)?;
+        let src = (sdp >> 12) as u8;
+        let dest = (sdp >> 4) as u8;
+        let prio = (sdp & 0x7) as u8;
+
+        let pgn = u32::from_str_radix(fields.next().ok_or(NMEA2000Error::RawFormatError)?, 16)?;
+
+        //Parse data, an empty message has no data field
+        let d = fields.next().unwrap_or("");
+        if fields.next().is_some() || !d.len().is_multiple_of(2) || !d.is_ascii() {
+            return Err(NMEA2000Error::RawFormatError);
+        }
+        let data = (0..d.len()).step_by(2)
+                               .map(|i| u8::from_str_radix(&d[i..i+2], 16))
+                               .collect::<Result<Vec<u8>,_>>()?;
+
+        Ok(Raw{
+            timestamp,
+            prio,
+            pgn,
+            src,
+            dest,
+            data
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::nmea::nmea2000::{From, Raw as _};
+
+    fn parse(line: &str) -> Result<Raw, NMEA2000Error>{
+        <Raw as From<String>>::from(&line.to_string())
+    }
+
+    #[test]
+    fn parse_line(){
+        let raw = parse("A173321.141 15FF2 1F801 A07DE618C005FBD5").unwrap();
+        assert_eq!(raw.pgn(), 129025);
+        assert_eq!((raw.src(), raw.dest(), raw.prio()), (0x15, 0xFF, 2));
+        assert_eq!((raw.timestamp().0, raw.timestamp().1), (17, 33));
+        assert_eq!(raw.data(), vec![0xA0, 0x7D, 0xE6, 0x18, 0xC0, 0x05, 0xFB, 0xD5]);
+    }
+
+    #[test]
+    fn seconds_since_start(){
+        //No valid time of the day, i.e., seconds since the device was started
+        let raw = parse("A3661.500 15FF2 1F801 A07DE618C005FBD5").unwrap();
+        assert_eq!(raw.timestamp(), (1, 1, 1.5));
+    }
+
+    #[test]
+    fn malformed_line(){
+        assert!(parse("173321.141 15FF2 1F801 A07DE618C005FBD5").is_err());
+        assert!(parse("A173321.141 15FF2 1F801 A07DE618C005FBD").is_err());
+        assert!(parse("A173321.141 15FF2").is_err());
+        assert!(parse("A173321.141 15FF2 1F801 A07DE618 C005FBD5").is_err());
+    }
+}
@@ -0,0 +1,117 @@
+//! Tools to read SeaSmart `$PCDIN` sentences. Implements the `N2kRaw` trait.
+//! 
+//! SeaSmart PCDIN format:
+//! 
+//!  `$PCDIN,pgn,timestamp,src,data*hh<CR><LF>`
+//! 
+//!  where:
+//! 
+//!  • pgn — PGN in hexadecimal format (6 digits)
+//! 
+//!  • timestamp — time of reception in milliseconds in hexadecimal format (8 digits)
+//! 
+//!  • src — source address in hexadecimal format (2 digits)
+//! 
+//!  • data — data bytes of the complete message in hexadecimal format without separators
+//! 
+//!  • hh — NMEA 0183 checksum
+//! 
+//! The sentence holds no priority and destination, they are set to 0 and 255 (global).
+use crate::nmea::types::{TData, TDest, TPgn, TPrio, TSrc, Timestamp};
+use crate::nmea::{nmea2000, timestamp_from_secs};
+use crate::nmea::nmea2000::NMEA2000Error;
+
+/// Holds a PCDIN message.
+pub struct Raw{
+    pub timestamp : Timestamp,
+    pub pgn : u32,
+    pub src : u8,
+    pub data : Vec<u8>
+}
+
+impl nmea2000::Raw for Raw{
+    #[inline(always)]
+    fn timestamp(&self) -> Timestamp { self.timestamp }
+    #[inline(always)]
+    fn src(&self) -> TSrc { self.src }
+    #[inline(always)]
+    fn dest(&self) -> TDest { 0xff }
+    #[inline(always)]
+    fn prio(&self) -> TPrio { 0 }
+    #[inline(always)]
+    fn pgn(&self) -> TPgn { self.pgn }
+    #[inline(always)]
+    fn data(&self) -> TData { self.data.clone() }
+
+    fn write(&self, m: &mut Box<dyn nmea2000::Message>) -> Result<(),NMEA2000Error>{
+        if m.is_fast() {
+            nmea2000::write_message(self, m)
+        } else {
+            nmea2000::write_frame(self, m)
+        }
+    }
+}
+
+impl nmea2000::From<String> for Raw{
+    fn from(s: &String) -> Result<Self, NMEA2000Error>{
+        let s = s.trim().strip_prefix("$PCDIN,").ok_or(NMEA2000Error::RawFormatError)?;
+
+        //Verify checksum if present
+        let s = match s.split_once('*'){
+            Some((sentence, checksum)) => {
+                let expected = u8::from_str_radix(checksum, 16)?;
+                if "PCDIN,".bytes().chain(sentence.bytes()).fold(0, |c, b| c ^ b) != expected {
+                    return Err(NMEA2000Error::ChecksumError);
+                }
+                sentence
+            }
+            None => s
+        };
+
+        let mut fields = s.split(',');
+        let pgn = u32::from_str_radix(fields.next().ok_or(NMEA2000Error::RawFormatError)?, 16)?;
+        let millis = u32::from_str_radix(fields.next().ok_or(NMEA2000Error::RawFormatError)?, 16)?;
+        let src = u8::from_str_radix(fields.next().ok_or(NMEA2000Error::RawFormatError)?, 16)?;
+        let d = fields.next().ok_or(NMEA2000Error::RawFormatError)?;
+        if fields.next().is_some() || !d.len().is_multiple_of(2) || !d.is_ascii() {
+            return Err(NMEA2000Error::RawFormatError);
+        }
+        let data = (0..d.len()).step_by(2)
+                               .map(|i| u8::from_str_radix(&d[i..i+2], 16))
+                               .collect::<Result<Vec<u8>,_>>()?;
+
+        Ok(Raw{
+            timestamp: timestamp_from_secs(millis as f64 / 1000.0),
+            pgn,
+            src,
+            data
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::nmea::nmea2000::{From, Raw as _};
+
+    fn parse(line: &str) -> Result<Raw, NMEA2000Error>{
+        <Raw as From<String>>::from(&line.to_string())
+    }
+
+    #[test]
+    fn parse_sentence(){
+        let raw = parse("$PCDIN,01F801,000C7E1B,15,A07DE618C005FBD5*23").unwrap();
+        assert_eq!(raw.pgn(), 129025);
+        assert_eq!(raw.src(), 0x15);
+        assert_eq!((raw.timestamp().0, raw.timestamp().1), (0, 13));
+        assert_eq!(raw.data(), vec![0xA0, 0x7D, 0xE6, 0x18, 0xC0, 0x05, 0xFB, 0xD5]);
+    }
+
+    #[test]
+    fn malformed_sentence(){
+        assert!(matches!(parse("$PCDIN,01F801,000C7E1B,15,A07DE618C005FBD5*24"), Err(NMEA2000Error::ChecksumError)));
+        assert!(parse("$PCDIN,01F801,000C7E1B,15,A07DE618C005FBD").is_err());
+        assert!(parse("$PCDIN,01F801,000C7E1B").is_err());
+        assert!(parse("$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230324,003.1,W*61").is_err());
+    }
+}
@@ -0,0 +1,92 @@
+//! Tools to read CAN frames from a Linux SocketCAN interface. Implements the `N2kRaw` trait.
+//! 
+//! Reading the frames from the socket itself is done by `CanSocket`, this module only
+//! holds the bus independent representation of a received frame.
+//! 
+//! NMEA 2000 uses extended (29-bit) CAN identifiers exclusively, i.e., the message
+//! identifier contains the NMEA 2000 PGN and the other fields in the same way as the
+//! msgid of the Yacht Devices Raw format.
+use std::fmt;
+
+use crate::nmea::types::{TData, TDest, TPgn, TPrio, TSrc, Timestamp};
+use crate::nmea::nmea2000;
+use crate::nmea::nmea2000::NMEA2000Error;
+
+/// A CAN frame as it was received from the bus.
+pub struct Frame{
+    /// Time of reception
+    pub timestamp : Timestamp,
+    /// 29-bit message identifier
+    pub id : u32,
+    /// Number of valid data bytes
+    pub len : u8,
+    /// Data bytes, only the first `len` bytes are valid
+    pub data : [u8;8]
+}
+
+/// Holds a SocketCAN message.
+/// 
+/// The values for priority, pgn, src and dest are derived.
+pub struct Raw{
+    //Parsed values
+    pub timestamp : Timestamp,
+    pub msgid : u32,
+    pub len : u8,
+    pub data : [u8;8],
+
+    //Derived values (ISO11783 Bits)
+    pub prio : u8,
+    pub pgn : u32,
+    pub src : u8,
+    pub dest : u8
+}
+
+impl nmea2000::Raw for Raw{
+    #[inline(always)]
+    fn timestamp(&self) -> Timestamp { self.timestamp }
+    #[inline(always)]
+    fn src(&self) -> TSrc { self.src }
+    #[inline(always)]
+    fn dest(&self) -> TDest { self.dest }
+    #[inline(always)]
+    fn prio(&self) -> TPrio { self.prio }
+    #[inline(always)]
+    fn pgn(&self) -> TPgn { self.pgn }
+    #[inline(always)]
+    fn data(&self) -> TData { self.data[..self.len as usize].to_vec() }
+
+    fn write(&self, m: &mut Box<dyn nmea2000::Message>) -> Result<(),NMEA2000Error>{
+        nmea2000::write_frame(self, m)
+    }
+}
+
+impl nmea2000::From<Frame> for Raw{
+    fn from(f: &Frame) -> Result<Self, NMEA2000Error>{
+        if f.len > 8 {
+            return Err(NMEA2000Error::RawFormatError);
+        }
+        let msgid = f.id & 0x1FFF_FFFF;
+        let (prio, pgn, src, dest) = nmea2000::decode_msgid(msgid);
+        Ok(Raw{
+            timestamp: f.timestamp,
+            msgid,
+            len: f.len,
+            data: f.data,
+            prio,
+            pgn,
+            src,
+            dest
+        })
+    }
+}
+
+/// Display trait implementation, same format as `candump` without timestamp.
+impl fmt::Display for Raw{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        write!(f,"{:08X}#",self.msgid)?;
+        for b in &self.data[..self.len as usize]{
+            write!(f,"{:02X}", b)?;
+        }
+        Ok(())
+    }
+}
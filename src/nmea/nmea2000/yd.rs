@@ -15,13 +15,18 @@
 //!  • b0..b7 — message data bytes (from 1 to 8) in hexadecimal format
 //! 
 //!  • `<CR><LF>`
+//! 
+//! Lines read from packet captures are preceded by the date of the capture, e.g.,
+//! `2021-06-16 17:33:21.141 R 09F80115 ...`, see [`with_capture_time`].
 use std::fmt;
 
+use chrono::NaiveDate;
+
 use crate::nmea::types::{TData, TDest, TPgn, TPrio, TSrc, Timestamp};
 use crate::nmea::nmea2000;
+use crate::nmea::{days_from_secs, timestamp_from_secs};
 use crate::nmea::nmea2000::NMEA2000Error;
 
-use std::cmp;
 use std::str::FromStr;
 
 /// Holds a YDRaw message.
@@ -29,6 +34,8 @@ use std::str::FromStr;
 /// The values for priority, pgn, src and dest are derived.
 pub struct Raw{
     //Parsed values
+    /// Days since January 1 1970 of lines read from packet captures
+    pub date : Option<u16>,
     pub timestamp : Timestamp,
     pub direction : YDRawDirection,
     pub msgid : u32,
@@ -45,6 +52,8 @@ impl nmea2000::Raw for Raw{
     #[inline(always)]
     fn timestamp(&self) -> Timestamp { self.timestamp }
     #[inline(always)]
+    fn date(&self) -> Option<u16> { self.date }
+    #[inline(always)]
     fn src(&self) -> TSrc { self.src }
     #[inline(always)]
     fn dest(&self) -> TDest { self.dest }
@@ -56,61 +65,7 @@ impl nmea2000::Raw for Raw{
     fn data(&self) -> TData { self.data.to_vec() }
 
     fn write(&self, m: &mut Box<dyn nmea2000::Message>) -> Result<(),NMEA2000Error>{
-        //Is this a fast message?
-        //(This part is optimized in the compiler and only present
-        // in messages which are consisting of several raw-packets)
-        if m.is_fast(){
-            //If we are just starting this new fast package
-            if (m.next_packet() == 0) && (self.data[0] & 0x1F == 0){
-                //Check if this packet has the same length as we expect to see
-                if m.bytes() != self.data[1] as usize {
-                    return Err(NMEA2000Error::UnexpectedPacketLength);
-                }
-                //Set values and the first 6 bytes for this package
-                *m.timestamp_mut() = self.timestamp;
-                *m.src_mut() = self.src;
-                *m.dest_mut() = self.dest;
-                *m.prio_mut() = self.prio;
-                *m.counter_mask_mut() = self.data[0];
-                *m.next_packet_mut() += 1;
-                *m.remaining_bytes_mut() = m.bytes() - 6;
-                m.data_mut().append(&mut self.data[2..8_usize].to_vec());
-            } else {
-                //This packet is already begun...
-                //If the packet is the next in series
-                if m.next_packet() == (m.counter_mask() ^ self.data[0]){
-                    let l = cmp::min(m.remaining_bytes()+1,8);
-                    m.data_mut().append(&mut self.data[1..l as usize].to_vec());
-                    *m.remaining_bytes_mut() -= cmp::min(m.remaining_bytes(),7);
-                    *m.next_packet_mut() += 1;
-                } else {
-                    //It seems that the previous sequence was not finished. Try to start a new sequence.
-                    //Check that only bits in sequence identifier (raw.data[0] & 0b00011111) and sequence
-                    //size with what we expect.
-                    if (self.data[0] & 0x1F == 0) && ((self.data[1] as usize ) == m.bytes() as usize){
-                        *m.timestamp_mut() = self.timestamp;
-                        *m.src_mut() = self.src;
-                        *m.dest_mut() = self.dest;
-                        *m.prio_mut() = self.prio;
-                        *m.counter_mask_mut() = self.data[0];
-                        *m.next_packet_mut() += 1;
-                        *m.remaining_bytes_mut() = m.bytes() - cmp::min(m.bytes(),6);
-                        m.data_mut().clear();
-                        m.data_mut().append(&mut self.data[2..8_usize].to_vec());
-                    } else {
-                        return Err(NMEA2000Error::PacketOutOfSequence);
-                    }
-                }
-            }
-        } else {
-            //Just a normal packet
-            *m.timestamp_mut() = self.timestamp;
-            *m.src_mut() = self.src;
-            *m.dest_mut() = self.dest;
-            *m.prio_mut() = self.prio;
-            m.data_mut().append(&mut self.data.to_vec());
-        }
-        Ok(())
+        nmea2000::write_frame(self, m)
     }    
 }
 
@@ -119,12 +74,20 @@ impl nmea2000::From<String> for Raw{
         // Split data fields
         let mut fields = s.split_whitespace();
         
-        //Parse time
-        let t = fields.next().ok_or(NMEA2000Error::RawFormatError)?;
+        //Parse the date of captured lines and time
+        let mut t = fields.next().ok_or(NMEA2000Error::RawFormatError)?;
+        let date = match parse_date(t){
+            Some(date) => {
+                t = fields.next().ok_or(NMEA2000Error::RawFormatError)?;
+                Some(date)
+            }
+            None => None,
+        };
+        let field = |r: std::ops::Range<usize>| t.get(r).ok_or(NMEA2000Error::RawFormatError);
         let timestamp = (
-            u8::from_str(&t[0..2])?,
-            u8::from_str(&t[3..5])?,
-            f32::from_str(&t[6..12])?
+            u8::from_str(field(0..2)?)?,
+            u8::from_str(field(3..5)?)?,
+            f32::from_str(field(6..12)?)?
         );
 
         //Get direction
@@ -140,24 +103,7 @@ impl nmea2000::From<String> for Raw{
         let msgid = u32::from_str_radix(m,16)?;
 
         //Derive values from msgid (ISO11783 Bits)
-        //Without the help of the canboat project 
-        //(https://github.com/canboat/canboat/) it would
-        //have been a lot harder to find out how this works.
-        let pf : u8 = (msgid >> 16) as u8;
-        let ps : u8 = (msgid >> 8) as u8;
-        let rdp : u8 = ((msgid >> 24) & 3) as u8;
-
-        let src = msgid as u8;
-        let prio = ((msgid >> 26) & 0x7) as u8;
-        
-        let (dest,pgn) : (u8,u32);
-        if pf < 240{
-            dest = ps;
-            pgn = ((rdp as u32) << 16) + ((pf as u32) << 8);
-        }else{
-            dest = 0xff;
-            pgn = ((rdp as u32) << 16) + ((pf as u32) << 8) + (ps as u32);
-        }
+        let (prio, pgn, src, dest) = nmea2000::decode_msgid(msgid);
 
         //Get 8 message bytes, no more, no less
         //At this stage the method is not checking if there are enough or too few
@@ -169,6 +115,7 @@ impl nmea2000::From<String> for Raw{
         }
         
         Ok(Raw{
+            date,
             timestamp, 
             direction,
             msgid, 
@@ -188,6 +135,9 @@ pub enum YDRawDirection { Received,Transmitted }
 /// Display trait implementation
 impl fmt::Display for Raw{
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        if let Some(date) = self.date{
+            write!(f,"{} ",epoch() + chrono::Duration::days(date as i64))?;
+        }
         write!(f,"{:02}:{:02}:{:0>6.3} ",self.timestamp.0, self.timestamp.1, self.timestamp.2)?;
         match self.direction {
             YDRawDirection::Received => { write!(f,"R ")?; }
@@ -199,4 +149,77 @@ impl fmt::Display for Raw{
         }
         write!(f,"{:02X}",self.data[7])
     }
-}
\ No newline at end of file
+}
+/// Replaces the time of the Yacht Devices RAW `line` with the capture time `secs` since
+/// January 1 1970, i.e., with the date and time a packet was captured. Lines without fields
+/// are returned unchanged.
+pub fn with_capture_time(line: &str, secs: f64) -> String{
+    let (h, m, s) = timestamp_from_secs(secs);
+    match line.find(char::is_whitespace){
+        Some(i) => format!("{} {:02}:{:02}:{:0>6.3}{}",
+                           epoch() + chrono::Duration::days(days_from_secs(secs) as i64),
+                           h, m, s, &line[i..]),
+        None => line.to_string(),
+    }
+}
+
+/// Removes the date of a captured line, i.e., returns the line as sent by a Yacht Devices
+/// gateway. Other lines are returned unchanged.
+pub fn without_date(line: &str) -> &str{
+    match line.split_once(' '){
+        Some((date, rest)) if parse_date(date).is_some() => rest,
+        _ => line,
+    }
+}
+
+/// Returns the days since January 1 1970 of a `YYYY-MM-DD` date
+fn parse_date(d: &str) -> Option<u16>{
+    if d.len() != 10 {
+        return None;
+    }
+    NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()
+        .map(|date| date.signed_duration_since(epoch()).num_days() as u16)
+}
+
+fn epoch() -> NaiveDate{
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::nmea::nmea2000::{From, Raw as _};
+
+    fn parse(line: &str) -> Result<Raw, NMEA2000Error>{
+        <Raw as From<String>>::from(&line.to_string())
+    }
+
+    #[test]
+    fn parse_line(){
+        let raw = parse("17:33:21.141 R 09F80115 A0 7D E6 18 C0 05 FB D5").unwrap();
+        assert_eq!(raw.pgn(), 129025);
+        assert_eq!(raw.src(), 0x15);
+        assert_eq!(raw.date(), None);
+        assert_eq!(raw.timestamp(), (17, 33, 21.141));
+        assert_eq!(raw.data(), vec![0xA0, 0x7D, 0xE6, 0x18, 0xC0, 0x05, 0xFB, 0xD5]);
+    }
+
+    #[test]
+    fn malformed_line(){
+        assert!(parse("17:33:21.141 X 09F80115 A0 7D E6 18 C0 05 FB D5").is_err());
+        assert!(parse("17:33 R 09F80115 A0 7D E6 18 C0 05 FB D5").is_err());
+        assert!(parse("17:33:21.141 R 09F8011G A0 7D E6 18 C0 05 FB D5").is_err());
+        assert!(parse("2021-06-16").is_err());
+    }
+
+    #[test]
+    fn captured_line(){
+        let line = with_capture_time("00:00:00.000 R 09F80115 A0 7D E6 18 C0 05 FB D5", 1623864801.5);
+        assert_eq!(line, "2021-06-16 17:33:21.500 R 09F80115 A0 7D E6 18 C0 05 FB D5");
+        let raw = parse(&line).unwrap();
+        assert_eq!(raw.date(), Some(18794));
+        assert_eq!(raw.timestamp(), (17, 33, 21.5));
+        assert_eq!(raw.to_string(), line);
+        assert_eq!(without_date(&line), "17:33:21.500 R 09F80115 A0 7D E6 18 C0 05 FB D5");
+    }
+}
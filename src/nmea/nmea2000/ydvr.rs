@@ -0,0 +1,135 @@
+//! Tools to read the binary `.DAT` recordings of the Yacht Devices Voyage Recorder YDVR-04.
+//! The records are read as [`socketcan::Frame`]s, i.e., use `socketcan::Raw` to parse them.
+//!
+//! YDVR record format (little endian):
+//!
+//!  `time id b0 .. bn`
+//!
+//!  where:
+//!
+//!  • time — 2 bytes, milliseconds since the beginning of the minute. `0xFFFF` marks a service
+//!    record of the recorder, which is skipped
+//!
+//!  • id — 4 bytes, bits 0 to 28 hold the 29-bit message identifier, bits 29 to 31 the number of
+//!    data bytes minus one
+//!
+//!  • b0..bn — 1 to 8 data bytes. Fast packets are recorded as single frames
+//!
+//! Since the records only hold the milliseconds, the minute is counted from the start of the file
+//! and synchronized with the time of day of the NMEA 2000 date messages in the recording.
+use std::io::{self, BufReader, Read};
+
+use crate::nmea::nmea2000;
+use crate::nmea::nmea2000::socketcan::Frame;
+use crate::nmea::timestamp_from_secs;
+
+/// Time of a service record
+pub const SERVICE_RECORD: u16 = 0xFFFF;
+/// PGN of the System Time message
+const SYSTEM_TIME_PGN: u32 = 126992;
+/// PGN of the Time & Date message
+const TIME_DATE_PGN: u32 = 129033;
+
+/// Reads the records of a YDVR recording.
+pub struct Reader<R: Read>{
+    reader: BufReader<R>,
+    /// Minutes since midnight
+    minute: u32,
+    /// Time of the previous record, to detect the start of a new minute
+    previous: u16,
+}
+
+impl<R: Read> Reader<R>{
+    pub fn new(r: R) -> Self{
+        Reader{reader: BufReader::new(r), minute: 0, previous: 0}
+    }
+
+    /// Reads the next record that holds a message, returns `Ok(None)` at the end of the file.
+    fn next_frame(&mut self) -> io::Result<Option<Frame>>{
+        loop{
+            let mut header = [0u8;6];
+            match self.reader.read_exact(&mut header){
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                r => r?
+            }
+            let time = u16::from_le_bytes([header[0], header[1]]);
+            let id = u32::from_le_bytes([header[2], header[3], header[4], header[5]]);
+            let len = (id >> 29) as u8 + 1;
+            let mut data = [0xFF;8];
+            self.reader.read_exact(&mut data[..len as usize])?;
+
+            if time == SERVICE_RECORD{
+                continue;
+            }
+            if time < self.previous{
+                self.minute += 1;
+            }
+            self.previous = time;
+
+            let id = id & 0x1FFFFFFF;
+            self.synchronize(id, time, &data[..len as usize]);
+            let secs = self.minute as f64 * 60.0 + time as f64 / 1000.0;
+            return Ok(Some(Frame{timestamp: timestamp_from_secs(secs), id, len, data}));
+        }
+    }
+
+    /// Sets the minute from the time of day of a date message recorded at `time`
+    fn synchronize(&mut self, id: u32, time: u16, data: &[u8]){
+        let (_, pgn, _, _) = nmea2000::decode_msgid(id);
+        let t = match (pgn, data.len()){
+            (SYSTEM_TIME_PGN, 8) => u32::from_le_bytes([data[4],data[5],data[6],data[7]]),
+            (TIME_DATE_PGN, 8) => u32::from_le_bytes([data[2],data[3],data[4],data[5]]),
+            _ => return,
+        };
+        //Time of the day in units of 0.0001s, values above are not available
+        if t < 864_000_000{
+            let secs = (t as f64 * 0.0001 - time as f64 / 1000.0).rem_euclid(86_400.0);
+            self.minute = (secs / 60.0).round() as u32;
+        }
+    }
+}
+
+impl<R: Read> Iterator for Reader<R>{
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item>{
+        self.next_frame().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    /// Record of a message with identifier `id` recorded at `time`
+    fn record(time: u16, id: u32, data: &[u8]) -> Vec<u8>{
+        let mut record = time.to_le_bytes().to_vec();
+        record.extend((id | (data.len() as u32 - 1) << 29).to_le_bytes());
+        record.extend(data);
+        record
+    }
+
+    #[test]
+    fn read_records(){
+        let mut file = record(SERVICE_RECORD, 0, b"YDVR v05");
+        //129033 of 12:00:00.5 on June 16 2021
+        let mut date = 18794u16.to_le_bytes().to_vec();
+        date.extend(432_005_000u32.to_le_bytes());
+        date.extend([0, 0]);
+        file.extend(record(500, 0x0DF80905, &date));
+        file.extend(record(1500, 0x09F80115, &[0xA0, 0x7D, 0xE6, 0x18, 0xC0, 0x05, 0xFB, 0xD5]));
+        let frames = Reader::new(&file[..]).collect::<io::Result<Vec<Frame>>>().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].timestamp, (12, 0, 0.5));
+        assert_eq!((frames[1].id, frames[1].len), (0x09F80115, 8));
+        assert_eq!(frames[1].timestamp, (12, 0, 1.5));
+    }
+
+    #[test]
+    fn truncated_record(){
+        let mut file = record(500, 0x09F80115, &[0xA0, 0x7D, 0xE6, 0x18, 0xC0, 0x05, 0xFB, 0xD5]);
+        file.truncate(10);
+        let mut reader = Reader::new(&file[..]);
+        assert_eq!(reader.next().unwrap().err().unwrap().kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,349 @@
+//! Reader for packet captures in pcap and pcapng format, e.g., written by tcpdump or Wireshark.
+//! Extracts the payloads of UDP packets to a given port together with their capture timestamps.
+//!
+//! Supported link types are Ethernet (with VLAN tags), Linux cooked capture v1 and v2,
+//! BSD loopback and raw IP, carrying IPv4 or IPv6. Fragmented IPv4 packets are skipped.
+use std::io::{self, Read};
+
+/// Magic number of a pcap file with timestamps in microseconds
+const PCAP_MAGIC_US: u32 = 0xA1B2C3D4;
+/// Magic number of a pcap file with timestamps in nanoseconds
+const PCAP_MAGIC_NS: u32 = 0xA1B23C4D;
+/// Block type of a pcapng section header block
+const PCAPNG_SECTION_HEADER: u32 = 0x0A0D0D0A;
+/// Byte order magic of a pcapng section header block
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1A2B3C4D;
+/// Block type of a pcapng interface description block
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 1;
+/// Block type of a pcapng enhanced packet block
+const PCAPNG_ENHANCED_PACKET: u32 = 6;
+/// Option code of the timestamp resolution of an interface
+const PCAPNG_IF_TSRESOL: u16 = 9;
+/// Records or blocks larger than this are considered corrupt
+const MAX_RECORD_SIZE: usize = 1 << 24;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const IP_PROTOCOL_UDP: u8 = 17;
+
+/// Format of the capture file, determined by its first bytes
+enum Format{
+    /// pcap with the number of timestamp fraction units per second
+    Pcap{big_endian: bool, resolution: f64, linktype: u32},
+    /// pcapng with the link type and timestamp resolution of every interface of the section
+    Pcapng{big_endian: bool, interfaces: Vec<(u32, f64)>},
+}
+
+/// UDP payload with the capture time in seconds since January 1 1970
+pub struct Packet{
+    pub timestamp: f64,
+    pub payload: Vec<u8>,
+}
+
+/// Iterates over the payloads of all UDP packets to `port` in a capture.
+pub struct UdpPayloads<R: Read>{
+    reader: R,
+    port: u16,
+    format: Option<Format>,
+}
+
+impl<R: Read> UdpPayloads<R>{
+    /// Returns a new reader for the capture in `reader`. The header is read with the first packet.
+    pub fn new(reader: R, port: u16) -> Self{
+        UdpPayloads{reader, port, format: None}
+    }
+
+    /// Reads the file header and determines the format of the capture
+    fn read_header(&mut self) -> io::Result<Format>{
+        let mut magic = [0u8;4];
+        self.reader.read_exact(&mut magic)?;
+        if u32::from_le_bytes(magic) == PCAPNG_SECTION_HEADER{
+            let big_endian = self.read_section_header()?;
+            return Ok(Format::Pcapng{big_endian, interfaces: Vec::new()});
+        }
+        let (big_endian, resolution) = match (u32::from_le_bytes(magic), u32::from_be_bytes(magic)){
+            (PCAP_MAGIC_US, _) => (false, 1e6),
+            (PCAP_MAGIC_NS, _) => (false, 1e9),
+            (_, PCAP_MAGIC_US) => (true, 1e6),
+            (_, PCAP_MAGIC_NS) => (true, 1e9),
+            _ => return Err(invalid("not a pcap or pcapng file")),
+        };
+        let mut header = [0u8;20];
+        self.reader.read_exact(&mut header)?;
+        let linktype = u32_at(&header, 16, big_endian) & 0x0FFFFFFF;
+        Ok(Format::Pcap{big_endian, resolution, linktype})
+    }
+
+    /// Reads the remainder of a pcapng section header block after its block type and
+    /// returns the byte order of the section
+    fn read_section_header(&mut self) -> io::Result<bool>{
+        let mut header = [0u8;8];
+        self.reader.read_exact(&mut header)?;
+        let big_endian = match u32::from_le_bytes([header[4],header[5],header[6],header[7]]){
+            PCAPNG_BYTE_ORDER_MAGIC => false,
+            m if m.swap_bytes() == PCAPNG_BYTE_ORDER_MAGIC => true,
+            _ => return Err(invalid("invalid pcapng byte order magic")),
+        };
+        let length = u32_at(&header, 0, big_endian) as usize;
+        if length < 12{
+            return Err(invalid("invalid pcapng block length"));
+        }
+        self.read_bytes(length - 12)?;
+        Ok(big_endian)
+    }
+
+    /// Reads `n` bytes
+    fn read_bytes(&mut self, n: usize) -> io::Result<Vec<u8>>{
+        if n > MAX_RECORD_SIZE{
+            return Err(invalid("record too large"));
+        }
+        let mut buffer = vec![0u8; n];
+        self.reader.read_exact(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Reads the next packet of the capture, returns `None` at the end of the file
+    fn read_packet(&mut self) -> io::Result<Option<Packet>>{
+        let format = match self.format.take(){
+            Some(f) => f,
+            None => match self.read_header(){
+                Ok(f) => f,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        };
+        let result = match format{
+            Format::Pcap{big_endian, resolution, linktype} => {
+                let packet = self.read_pcap_record(big_endian, resolution, linktype);
+                self.format = Some(Format::Pcap{big_endian, resolution, linktype});
+                packet
+            }
+            Format::Pcapng{mut big_endian, mut interfaces} => {
+                let packet = self.read_pcapng_block(&mut big_endian, &mut interfaces);
+                self.format = Some(Format::Pcapng{big_endian, interfaces});
+                packet
+            }
+        };
+        match result{
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            r => r.map(Some),
+        }
+    }
+
+    /// Reads a pcap record, returns an empty payload if it is not a UDP packet to `port`
+    fn read_pcap_record(&mut self, big_endian: bool, resolution: f64, linktype: u32) -> io::Result<Packet>{
+        let mut header = [0u8;16];
+        self.reader.read_exact(&mut header)?;
+        let timestamp = u32_at(&header, 0, big_endian) as f64
+            + u32_at(&header, 4, big_endian) as f64 / resolution;
+        let data = self.read_bytes(u32_at(&header, 8, big_endian) as usize)?;
+        Ok(Packet{timestamp, payload: udp_payload(linktype, &data, self.port).unwrap_or_default()})
+    }
+
+    /// Reads a pcapng block, returns an empty payload if the block is not a UDP packet to `port`.
+    /// A section header block updates the byte order and resets the interfaces.
+    fn read_pcapng_block(&mut self, big_endian: &mut bool, interfaces: &mut Vec<(u32, f64)>)
+        -> io::Result<Packet>
+    {
+        let empty = Packet{timestamp: 0.0, payload: Vec::new()};
+        let mut header = [0u8;4];
+        self.reader.read_exact(&mut header)?;
+        let block_type = u32_at(&header, 0, *big_endian);
+        if block_type == PCAPNG_SECTION_HEADER{
+            interfaces.clear();
+            *big_endian = self.read_section_header()?;
+            return Ok(empty);
+        }
+        self.reader.read_exact(&mut header)?;
+        let length = u32_at(&header, 0, *big_endian) as usize;
+        if length < 12 || !length.is_multiple_of(4){
+            return Err(invalid("invalid pcapng block length"));
+        }
+        let body = self.read_bytes(length - 8)?;
+        let body = &body[..body.len() - 4];
+
+        match block_type{
+            PCAPNG_INTERFACE_DESCRIPTION if body.len() >= 8 => {
+                let linktype = u16_at(body, 0, *big_endian) as u32;
+                let resolution = if_tsresol(&body[8..], *big_endian);
+                interfaces.push((linktype, resolution));
+                Ok(empty)
+            }
+            PCAPNG_ENHANCED_PACKET if body.len() >= 20 => {
+                let interface = u32_at(body, 0, *big_endian) as usize;
+                let (linktype, resolution) = *interfaces.get(interface)
+                    .ok_or_else(|| invalid("packet of undefined interface"))?;
+                let time = (u32_at(body, 4, *big_endian) as u64) << 32
+                    | u32_at(body, 8, *big_endian) as u64;
+                let captured = (u32_at(body, 12, *big_endian) as usize).min(body.len() - 20);
+                let data = &body[20..20 + captured];
+                Ok(Packet{
+                    timestamp: time as f64 / resolution,
+                    payload: udp_payload(linktype, data, self.port).unwrap_or_default()
+                })
+            }
+            _ => Ok(empty),
+        }
+    }
+}
+
+impl<R: Read> Iterator for UdpPayloads<R>{
+    type Item = io::Result<Packet>;
+
+    fn next(&mut self) -> Option<Self::Item>{
+        loop{
+            match self.read_packet(){
+                Ok(Some(packet)) if packet.payload.is_empty() => continue,
+                r => return r.transpose(),
+            }
+        }
+    }
+}
+
+/// Returns the timestamp units per second from the options of an interface description block
+fn if_tsresol(mut options: &[u8], big_endian: bool) -> f64{
+    while options.len() >= 4{
+        let code = u16_at(options, 0, big_endian);
+        let length = u16_at(options, 2, big_endian) as usize;
+        if code == 0 || options.len() < 4 + length{
+            break;
+        }
+        if code == PCAPNG_IF_TSRESOL && length >= 1{
+            let v = options[4];
+            return if v & 0x80 != 0 { 2f64.powi((v & 0x7F) as i32) } else { 10f64.powi(v as i32) };
+        }
+        options = &options[(4 + length.div_ceil(4) * 4).min(options.len())..];
+    }
+    1e6
+}
+
+/// Returns the UDP payload of the link layer frame `data` if it is sent to `port`
+fn udp_payload(linktype: u32, data: &[u8], port: u16) -> Option<Vec<u8>>{
+    let (ethertype, ip) = match linktype{
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+            let mut ethertype = u16_at(data.get(..offset + 2)?, offset, true);
+            while ethertype == ETHERTYPE_VLAN{
+                offset += 4;
+                ethertype = u16_at(data.get(..offset + 2)?, offset, true);
+            }
+            (ethertype, data.get(offset + 2..)?)
+        }
+        LINKTYPE_LINUX_SLL => (u16_at(data.get(..16)?, 14, true), &data[16..]),
+        LINKTYPE_LINUX_SLL2 => (u16_at(data.get(..20)?, 0, true), &data[20..]),
+        LINKTYPE_NULL => match data.get(..4)?{
+            [2,0,0,0] | [0,0,0,2] => (ETHERTYPE_IPV4, &data[4..]),
+            _ => (ETHERTYPE_IPV6, &data[4..]),
+        },
+        LINKTYPE_RAW => match data.first()? >> 4{
+            4 => (ETHERTYPE_IPV4, data),
+            _ => (ETHERTYPE_IPV6, data),
+        },
+        _ => return None,
+    };
+
+    let udp = match ethertype{
+        ETHERTYPE_IPV4 => {
+            let header_length = (*ip.first()? & 0x0F) as usize * 4;
+            let fragment = u16_at(ip.get(..8)?, 6, true);
+            if ip.get(9)? != &IP_PROTOCOL_UDP || fragment & 0x3FFF != 0{
+                return None;
+            }
+            ip.get(header_length..)?
+        }
+        ETHERTYPE_IPV6 => {
+            if ip.get(6)? != &IP_PROTOCOL_UDP{
+                return None;
+            }
+            ip.get(40..)?
+        }
+        _ => return None,
+    };
+
+    if u16_at(udp.get(..8)?, 2, true) != port{
+        return None;
+    }
+    let length = (u16_at(udp, 4, true) as usize).clamp(8, udp.len());
+    Some(udp[8..length].to_vec())
+}
+
+fn u16_at(data: &[u8], offset: usize, big_endian: bool) -> u16{
+    let bytes = [data[offset], data[offset + 1]];
+    if big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) }
+}
+
+fn u32_at(data: &[u8], offset: usize, big_endian: bool) -> u32{
+    let bytes = [data[offset], data[offset + 1], data[offset + 2], data[offset + 3]];
+    if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+}
+
+fn invalid(message: &str) -> io::Error{
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ethernet frame of an IPv4 UDP packet to `port`
+    fn udp(port: u16, payload: &[u8]) -> Vec<u8>{
+        let mut frame = vec![0xFF,0xFF,0xFF,0xFF,0xFF,0xFF, 0x00,0x11,0x22,0x33,0x44,0x55, 0x08,0x00];
+        let length = (28 + payload.len()) as u16;
+        frame.extend([0x45,0x00]);
+        frame.extend(length.to_be_bytes());
+        frame.extend([0,0, 0x40,0x00, 64, IP_PROTOCOL_UDP, 0,0, 192,168,4,1, 192,168,4,255]);
+        frame.extend(5000u16.to_be_bytes());
+        frame.extend(port.to_be_bytes());
+        frame.extend((8 + payload.len() as u16).to_be_bytes());
+        frame.extend([0,0]);
+        frame.extend(payload);
+        frame
+    }
+
+    /// pcap file with microsecond timestamps of the Ethernet `frames`
+    fn pcap(frames: &[(u32, u32, Vec<u8>)]) -> Vec<u8>{
+        let mut file = PCAP_MAGIC_US.to_le_bytes().to_vec();
+        file.extend([2,0, 4,0, 0,0,0,0, 0,0,0,0, 0xFF,0xFF,0,0]);
+        file.extend(LINKTYPE_ETHERNET.to_le_bytes());
+        for (secs, micros, frame) in frames{
+            file.extend(secs.to_le_bytes());
+            file.extend(micros.to_le_bytes());
+            file.extend((frame.len() as u32).to_le_bytes());
+            file.extend((frame.len() as u32).to_le_bytes());
+            file.extend(frame);
+        }
+        file
+    }
+
+    #[test]
+    fn udp_payloads(){
+        let file = pcap(&[
+            (1623864801, 500000, udp(1457, b"17:33:21.141 R 09F80115 A0 7D E6 18 C0 05 FB D5\r\n")),
+            (1623864802, 0, udp(53, b"noise")),
+        ]);
+        let packets = UdpPayloads::new(&file[..], 1457).collect::<io::Result<Vec<Packet>>>().unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].timestamp, 1623864801.5);
+        assert!(packets[0].payload.starts_with(b"17:33:21.141 R 09F80115"));
+    }
+
+    #[test]
+    fn truncated_record(){
+        let mut file = pcap(&[(1623864801, 0, udp(1457, b"17:33:21.141 R 09F80115 A0"))]);
+        file.truncate(file.len() - 10);
+        assert_eq!(UdpPayloads::new(&file[..], 1457).count(), 0);
+    }
+
+    #[test]
+    fn not_a_capture(){
+        let mut packets = UdpPayloads::new(&b"17:33:21.141 R 09F80115 A0 7D E6 18"[..], 1457);
+        assert_eq!(packets.next().unwrap().err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+}
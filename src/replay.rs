@@ -0,0 +1,105 @@
+//! Real-time replay of recorded inputs.
+use crate::nmea::nmea2000;
+
+use std::io;
+use std::marker;
+use std::net::{ToSocketAddrs, UdpSocket, SocketAddr};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Paces the items of a recorded input according to the timestamps of their [`Raw`](nmea2000::Raw)
+/// type `T`, i.e., items are returned at the speed they were recorded multiplied by `speed`.
+/// 
+/// Items that can not be read as `T` are returned immediately and left to the parser.
+pub struct Replay<T,U,I>{
+    input: I,
+    speed: f64,
+    /// Recorded time and wall clock of the first item
+    start: Option<(f64, Instant)>,
+    /// Recorded time of the previous item, used to detect midnight
+    previous: f64,
+    /// Seconds added to the recorded time after midnight
+    day_offset: f64,
+    _raw_type: marker::PhantomData<T>,
+    _ingest_type: marker::PhantomData<U>
+}
+
+impl<T,U,I> Replay<T,U,I>{
+    pub fn new(input: I, speed: f64) -> Self{
+        Replay{
+            input,
+            speed,
+            start: None,
+            previous: 0.0,
+            day_offset: 0.0,
+            _raw_type: marker::PhantomData,
+            _ingest_type: marker::PhantomData
+        }
+    }
+}
+
+impl<T,U,I> Iterator for Replay<T,U,I>
+    where
+        I: Iterator<Item=io::Result<U>>,
+        T: nmea2000::Raw + nmea2000::From<U>,
+    {
+    type Item = io::Result<U>;
+
+    fn next(&mut self) -> Option<Self::Item>{
+        let item = self.input.next()?;
+        if let Ok(Ok(raw)) = item.as_ref().map(|u| T::from(u)){
+            let (h, m, s) = raw.timestamp();
+            let mut t = h as f64 * 3600.0 + m as f64 * 60.0 + s as f64;
+            //Timestamps only hold the time of day
+            if t + self.day_offset < self.previous - 43_200.0{
+                self.day_offset += 86_400.0;
+            }
+            t += self.day_offset;
+            self.previous = t;
+
+            let (t0, start) = *self.start.get_or_insert((t, Instant::now()));
+            if t > t0{
+                let target = start + Duration::from_secs_f64((t - t0) / self.speed);
+                let now = Instant::now();
+                if target > now{
+                    thread::sleep(target - now);
+                }
+            }
+        }
+        Some(item)
+    }
+}
+
+/// Sends every line of the input as UDP packet to an address, e.g., a broadcast
+/// address, in the same way a Yacht Devices gateway does. The date of lines read from
+/// packet captures is not sent.
+pub struct Broadcast<I>{
+    input: I,
+    socket: UdpSocket,
+    address: SocketAddr
+}
+
+impl<I> Broadcast<I>{
+    pub fn new<A: ToSocketAddrs>(input: I, address: A) -> io::Result<Self>{
+        let address = address.to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address"))?;
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.set_broadcast(true)?;
+        Ok(Broadcast{input, socket, address})
+    }
+}
+
+impl<I: Iterator<Item=io::Result<String>>> Iterator for Broadcast<I>{
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item>{
+        let item = self.input.next()?;
+        if let Ok(line) = &item{
+            if let Err(e) = self.socket.send_to(format!("{}\r\n", nmea2000::yd::without_date(line)).as_bytes(), self.address){
+                return Some(Err(e));
+            }
+        }
+        Some(item)
+    }
+}
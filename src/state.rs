@@ -1,13 +1,64 @@
 //! State of the navigational data.
 use crate::nmea::types::Timestamp;
-use crate::nmea::nmea2000;
-use crate::nmea::{MessageValue,Float};
+use crate::nmea::{MessageValue,Float,TEMPERATURE_SEA,TEMPERATURE_OUTSIDE,HUMIDITY_OUTSIDE,FLUID_FUEL,
+                  HEADING_TRUE,HEADING_MAGNETIC};
+use crate::vessels::Vessels;
+use crate::collision::Alarms;
 
+use std::collections::BTreeMap;
 use std::f64::consts::PI;
 use std::fmt;
+use std::io::{self, Write};
 use std::time::SystemTime;
 
-use chrono::{Datelike,NaiveDateTime};
+use chrono::{DateTime,Datelike,NaiveDateTime};
+
+/// Default number of engine instances that are kept, e.g., port and starboard
+const ENGINES: usize = 2;
+/// Default number of fuel tank instances that are kept
+const FUEL_TANKS: usize = 2;
+/// Default number of battery instances that are kept, e.g., house and engine start battery
+const BATTERIES: usize = 2;
+/// Longest time without SOG in seconds that is bridged by the SOG trip distance
+const MAX_SOG_GAP: f32 = 60.0;
+
+/// Latest values of an engine
+#[derive(Clone, Copy)]
+pub struct Engine{
+    /// Engine speed in revolutions per minute
+    pub rpm : f32,
+    /// Coolant temperature in degrees Celsius
+    pub coolant_temperature : f32,
+    /// Fuel rate in liters per hour
+    pub fuel_rate : f32,
+}
+
+/// Values of an engine that was not received yet
+impl Default for Engine{
+    fn default() -> Self{
+        Engine{rpm: f32::NAN, coolant_temperature: f32::NAN, fuel_rate: f32::NAN}
+    }
+}
+
+/// Latest values of a battery bank
+#[derive(Clone, Copy)]
+pub struct Battery{
+    /// Voltage in Volt
+    pub voltage : f32,
+    /// Current in Ampere, negative when discharging
+    pub current : f32,
+    /// Temperature in degrees Celsius
+    pub temperature : f32,
+    /// State of charge in percent
+    pub state_of_charge : f32,
+}
+
+/// Values of a battery bank that was not received yet
+impl Default for Battery{
+    fn default() -> Self{
+        Battery{voltage: f32::NAN, current: f32::NAN, temperature: f32::NAN, state_of_charge: f32::NAN}
+    }
+}
 
 /// Keeps the latest values of the navigational data.
 pub struct State{
@@ -25,12 +76,44 @@ pub struct State{
     pub awa : f32,
     /// Apparent wind speed in knots
     pub aws : f32,
+    /// True wind angle referenced to the water in degrees
+    pub twa : f32,
+    /// True wind speed referenced to the water in knots
+    pub tws : f32,
+    /// True wind angle referenced to the ground in degrees
+    pub gwa : f32,
+    /// True wind speed referenced to the ground in knots
+    pub gws : f32,
+    /// True wind direction referenced to the ground in degrees
+    pub twd : f32,
+    /// Magnetic wind direction referenced to the ground in degrees
+    pub mwd : f32,
     /// Latitude
     pub latitude : f32,
     /// Longitude
     pub longitude : f32,
-    /// Heading in degrees
-    pub hdg : f32,
+    /// Fix type of the position, 0 no fix, 1 GNSS, 2 DGNSS, 3 precise, 4 RTK fixed, 5 RTK float, 6 estimated
+    pub fix : u8,
+    /// Number of satellites used in the fix
+    pub satellites : u8,
+    /// Number of satellites in view
+    pub satellites_in_view : u8,
+    /// Horizontal dilution of precision
+    pub hdop : f32,
+    /// Position dilution of precision
+    pub pdop : f32,
+    /// Vertical dilution of precision
+    pub vdop : f32,
+    /// Geoidal separation in meters
+    pub geoidal_separation : f32,
+    /// True heading in degrees
+    pub hdg_true : f32,
+    /// Magnetic heading in degrees
+    pub hdg_magnetic : f32,
+    /// Magnetic variation in degrees, east is positive
+    pub variation : f32,
+    /// Flag if we have received a magnetic variation, i.e., if one heading can be derived from the other.
+    got_variation : bool,
     /// Course over ground in degrees
     pub cog : f32,
     /// Speed over ground in knots
@@ -47,9 +130,56 @@ pub struct State{
     pub roll : f32,
     /// Angle of rudder deflection in degrees
     pub rudder_angle : f32,
+    /// Depth in meters, below the waterline or the keel depending on the transducer offset
+    pub depth : f32,
+    /// Sea water temperature in degrees Celsius
+    pub water_temperature : f32,
+    /// Outside air temperature in degrees Celsius
+    pub air_temperature : f32,
+    /// Atmospheric pressure in hPa
+    pub pressure : f32,
+    /// Outside relative humidity in percent
+    pub humidity : f32,
+    /// Total distance through water of the log in nautical miles
+    pub log : f32,
+    /// Trip distance through water of the log in nautical miles
+    pub trip_log : f32,
+    /// Distance over ground since start, integrated from the SOG, in nautical miles
+    pub sog_trip : f32,
+    /// Seconds since midnight of the latest SOG
+    sog_time : Option<f32>,
+    /// Cross track error in nautical miles, positive to the right of the course
+    pub xte : f32,
+    /// True bearing to the destination waypoint in degrees
+    pub btw : f32,
+    /// Distance to the destination waypoint in nautical miles
+    pub dtw : f32,
+    /// Velocity made good towards the destination waypoint in knots
+    pub vmg_wp : f32,
+    /// Name of the destination waypoint, empty if not known
+    pub destination : String,
+    /// Number of the destination waypoint
+    destination_id : Option<u32>,
+    /// Names of the waypoints of the received routes by number
+    waypoints : BTreeMap<u32, String>,
+    /// Engines by instance, see [`State::instances`]
+    pub engines : Vec<Engine>,
+    /// Level of the fuel tanks by instance in percent, see [`State::instances`]
+    pub fuel_levels : Vec<f32>,
+    /// Battery banks by instance, see [`State::instances`]
+    pub batteries : Vec<Battery>,
+    /// AIS targets
+    pub vessels : Vessels,
+    /// Collision alarms for the AIS targets, if enabled
+    pub alarms : Option<Alarms>,
+
+    /// Label of the input that delivered the latest update
+    pub source : String,
 
     /// Flag if we should use the system date.
     pub sys_date : bool,
+    /// Flag if the label of the input is written as additional column.
+    pub labels : bool,
     /// Flag if we have received a date/time value completely,
     /// i.e., we know that when we have read "localoffset".
     pub got_nmea_date: bool
@@ -67,28 +197,62 @@ fn to_knots(val: f32) -> f32{
     val * 1.943_844_6
 }
 
+/// Helper function to normalize an angle in degrees to [0, 360)
+#[inline(always)]
+fn normalize_degrees(val: f32) -> f32{
+    val.rem_euclid(360.0)
+}
+
+/// Helper function to convert between meters and nautical miles
+#[inline(always)]
+fn to_nautical_miles(val: f32) -> f32{
+    val / 1852.0
+}
+
+/// Helper function to convert between Kelvin and degrees Celsius
+#[inline(always)]
+fn to_celsius(val: f32) -> f32{
+    val - 273.15
+}
+
 /// Helper function to convert days, seconds and offset to a NaiveDateTime
 #[inline(always)]
 fn to_date_time(days: u16, seconds: f32, localoffset: i16) -> NaiveDateTime{
-    NaiveDateTime::from_timestamp(days as i64 * 86_400 
-                                  + seconds as i64
-                                  + (localoffset * 60) as i64,0)
+    DateTime::from_timestamp(days as i64 * 86_400 
+                             + seconds as i64
+                             + (localoffset * 60) as i64,0).unwrap_or_default().naive_utc()
 }
 
 impl State {
     /// Create new empty State
     pub fn new(sys_date: bool) -> State{
         State{
-            date_time: NaiveDateTime::from_timestamp(0,0),
+            date_time: DateTime::from_timestamp(0,0).unwrap_or_default().naive_utc(),
             days: 0,
             seconds: 0.0,
             localoffset: 0,
             timestamp: (0,0,0.0),
             awa: 0.0,
             aws: 0.0,
+            twa: f32::NAN,
+            tws: f32::NAN,
+            gwa: f32::NAN,
+            gws: f32::NAN,
+            twd: f32::NAN,
+            mwd: f32::NAN,
             latitude: 0.0,
             longitude: 0.0,
-            hdg: 0.0,
+            fix: 0,
+            satellites: 0,
+            satellites_in_view: 0,
+            hdop: f32::NAN,
+            pdop: f32::NAN,
+            vdop: f32::NAN,
+            geoidal_separation: f32::NAN,
+            hdg_true: f32::NAN,
+            hdg_magnetic: f32::NAN,
+            variation: f32::NAN,
+            got_variation: false,
             cog: 0.0,
             sog: 0.0,
             stw: 0.0,
@@ -97,18 +261,72 @@ impl State {
             yaw: 0.0,
             roll: 0.0,
             rudder_angle: 0.0,
+            depth: f32::NAN,
+            water_temperature: f32::NAN,
+            air_temperature: f32::NAN,
+            pressure: f32::NAN,
+            humidity: f32::NAN,
+            log: f32::NAN,
+            trip_log: f32::NAN,
+            sog_trip: 0.0,
+            sog_time: None,
+            xte: f32::NAN,
+            btw: f32::NAN,
+            dtw: f32::NAN,
+            vmg_wp: f32::NAN,
+            destination: String::new(),
+            destination_id: None,
+            waypoints: BTreeMap::new(),
+            engines: vec![Engine::default(); ENGINES],
+            fuel_levels: vec![f32::NAN; FUEL_TANKS],
+            batteries: vec![Battery::default(); BATTERIES],
+            vessels: Vessels::new(),
+            alarms: None,
+            source: String::new(),
             sys_date,
+            labels: false,
             got_nmea_date: false, 
         }
     }
 
+    /// Set the number of engine, fuel tank and battery instances that are kept and written. Values
+    /// of higher instances are ignored.
+    pub fn instances(&mut self, engines: usize, fuel_tanks: usize, batteries: usize){
+        self.engines = vec![Engine::default(); engines];
+        self.fuel_levels = vec![f32::NAN; fuel_tanks];
+        self.batteries = vec![Battery::default(); batteries];
+    }
+
     /// Print the headline for a CSV document containig all fields seperated by `;`
-    pub fn headline() -> String{
-        String::from("time;awa;aws;latitude;longitude;hdg;cog;sog;stw;rot;pitch;yaw;roll;rudder_angle")
+    pub fn headline(&self) -> String{
+        let mut headline = String::from("time;awa;aws;twa;tws;gwa;gws;twd;mwd;latitude;longitude;fix;satellites;satellites_in_view;hdop;pdop;vdop;geoidal_separation;hdg_true;hdg_magnetic;variation;cog;sog;stw;rot;pitch;yaw;roll;rudder_angle;depth;water_temperature;air_temperature;pressure;humidity;log;trip_log;sog_trip;xte;btw;dtw;vmg_wp;destination");
+        for i in 0..self.engines.len(){
+            headline.push_str(&format!(";rpm_{i};coolant_temperature_{i};fuel_rate_{i}"));
+        }
+        for i in 0..self.fuel_levels.len(){
+            headline.push_str(&format!(";fuel_level_{i}"));
+        }
+        for i in 0..self.batteries.len(){
+            headline.push_str(&format!(";voltage_{i};current_{i};battery_temperature_{i};state_of_charge_{i}"));
+        }
+        if self.labels{
+            headline.push_str(";source");
+        }
+        headline
+    }
+
+    /// Update the state with the values of a nmea message received by input `source`
+    pub fn update_from(&mut self, source: &str, values: Vec<MessageValue>){
+        if self.source != source{
+            self.source = source.to_string();
+        }
+        self.update(values);
     }
-    /// Update the state with a nmea message value
-    pub fn update(&mut self, message: Box<dyn nmea2000::Message>){
-        for entry in message.values(){
+    /// Update the state with the values of a nmea message
+    pub fn update(&mut self, values: Vec<MessageValue>){
+        let sog = self.sog;
+        let mut sog_updated = false;
+        for entry in values{
             match entry{
                 MessageValue::Timestamp(t) => self.timestamp = t,
                 MessageValue::Date(d) => {  self.days = d;
@@ -123,50 +341,339 @@ impl State {
                                                 }
                 MessageValue::WindSpeed(Float::F16(aws)) => self.aws = to_knots(aws),
                 MessageValue::WindAngle(Float::F16(awa)) => self.awa = to_degrees(awa),
+                MessageValue::TrueWindSpeed(Float::F16(tws)) => self.tws = to_knots(tws),
+                MessageValue::TrueWindAngle(Float::F16(twa)) => self.twa = to_degrees(twa),
+                MessageValue::GroundWindSpeed(Float::F16(gws)) => self.gws = to_knots(gws),
+                MessageValue::GroundWindAngle(Float::F16(gwa)) => self.gwa = to_degrees(gwa),
+                //The direction in the other reference is derived with the latest variation
+                MessageValue::WindDirection(HEADING_TRUE, Float::F16(twd)) => {
+                    self.twd = normalize_degrees(to_degrees(twd));
+                    if self.got_variation{
+                        self.mwd = normalize_degrees(self.twd - self.variation);
+                    }
+                }
+                MessageValue::WindDirection(HEADING_MAGNETIC, Float::F16(mwd)) => {
+                    self.mwd = normalize_degrees(to_degrees(mwd));
+                    if self.got_variation{
+                        self.twd = normalize_degrees(self.mwd + self.variation);
+                    }
+                }
                 MessageValue::Latitude(Float::F32(lat)) => self.latitude = lat,
                 MessageValue::Longitude(Float::F32(long)) => self.longitude = long,
                 MessageValue::Latitude(Float::F64(lat)) => self.latitude = lat as f32,
                 MessageValue::Longitude(Float::F64(long)) => self.longitude = long as f32,
-                MessageValue::Heading(Float::F16(hdg)) => self.hdg = to_degrees(hdg),
+                MessageValue::GnssMethod(fix) => self.fix = fix,
+                MessageValue::Satellites(n) => self.satellites = n,
+                MessageValue::SatellitesInView(n) => self.satellites_in_view = n,
+                MessageValue::Hdop(Float::F16(hdop)) => self.hdop = hdop,
+                MessageValue::Pdop(Float::F16(pdop)) => self.pdop = pdop,
+                MessageValue::Vdop(Float::F16(vdop)) => self.vdop = vdop,
+                MessageValue::GeoidalSeparation(Float::F32(separation)) => self.geoidal_separation = separation,
+                //The heading in the other reference is derived with the latest variation
+                MessageValue::Heading(HEADING_TRUE, Float::F16(hdg)) => {
+                    self.hdg_true = normalize_degrees(to_degrees(hdg));
+                    if self.got_variation{
+                        self.hdg_magnetic = normalize_degrees(self.hdg_true - self.variation);
+                    }
+                }
+                MessageValue::Heading(HEADING_MAGNETIC, Float::F16(hdg)) => {
+                    self.hdg_magnetic = normalize_degrees(to_degrees(hdg));
+                    if self.got_variation{
+                        self.hdg_true = normalize_degrees(self.hdg_magnetic + self.variation);
+                    }
+                }
+                //Headings with an erroneous or unknown reference are not used
+                MessageValue::Heading(_, _) => (),
+                MessageValue::Variation(Float::F16(variation)) => {
+                    self.variation = to_degrees(variation);
+                    self.got_variation = true;
+                }
                 MessageValue::CourseOverGround(Float::F16(cog)) => self.cog = to_degrees(cog),
-                MessageValue::SpeedOverGround(Float::F16(sog)) => self.sog = to_knots(sog),
+                MessageValue::SpeedOverGround(Float::F16(sog)) => {
+                    self.sog = to_knots(sog);
+                    sog_updated = true;
+                }
                 MessageValue::SpeedThroughWater(Float::F16(stw)) => self.stw = to_knots(stw),
                 MessageValue::RateOfTurn(Float::F32(rot)) => self.rot = to_degrees(rot),
                 MessageValue::Yaw(Float::F16(yaw)) => self.yaw = to_degrees(yaw),
                 MessageValue::Pitch(Float::F16(pitch)) => self.pitch = to_degrees(pitch),
                 MessageValue::Roll(Float::F16(roll)) => self.roll = to_degrees(roll),
-                //sanity check if plausible value for rudder angle
-                MessageValue::RudderAngle(Float::F16(ra)) => if(ra <= PI as f32) && (ra >= -PI as f32){
-                                                                self.rudder_angle = to_degrees(ra);
-                                                             }
-                _ => unimplemented!(),
+                MessageValue::RudderAngle(Float::F16(ra)) => self.rudder_angle = to_degrees(ra),
+                MessageValue::Depth(Float::F32(depth)) => self.depth = depth,
+                MessageValue::Log(Float::F32(log)) => self.log = to_nautical_miles(log),
+                MessageValue::CrossTrackError(Float::F32(xte)) => self.xte = to_nautical_miles(xte),
+                MessageValue::DistanceToWaypoint(Float::F32(dtw)) => self.dtw = to_nautical_miles(dtw),
+                MessageValue::BearingToWaypoint(HEADING_TRUE, Float::F16(btw)) => 
+                    self.btw = normalize_degrees(to_degrees(btw)),
+                //Only a magnetic bearing with known variation can be converted
+                MessageValue::BearingToWaypoint(HEADING_MAGNETIC, Float::F16(btw)) if self.got_variation => 
+                    self.btw = normalize_degrees(to_degrees(btw) + self.variation),
+                MessageValue::BearingToWaypoint(_, _) => (),
+                MessageValue::WaypointClosingVelocity(Float::F16(vmg)) => self.vmg_wp = to_knots(vmg),
+                MessageValue::DestinationWaypoint(id) if self.destination_id != Some(id) => {
+                    self.destination_id = Some(id);
+                    self.destination = self.waypoints.get(&id).cloned().unwrap_or_default();
+                }
+                MessageValue::Waypoint(id, name) => {
+                    if self.destination_id == Some(id){
+                        self.destination = name.clone();
+                    }
+                    self.waypoints.insert(id, name);
+                }
+                MessageValue::TripLog(Float::F32(trip)) => self.trip_log = to_nautical_miles(trip),
+                MessageValue::Temperature(TEMPERATURE_SEA, Float::F16(t) | Float::F32(t)) => 
+                    self.water_temperature = to_celsius(t),
+                MessageValue::Temperature(TEMPERATURE_OUTSIDE, Float::F16(t) | Float::F32(t)) => 
+                    self.air_temperature = to_celsius(t),
+                //Other temperature sources, e.g., engine room or refrigeration, are not kept
+                MessageValue::Temperature(_, _) => (),
+                MessageValue::Humidity(HUMIDITY_OUTSIDE, Float::F16(h)) => self.humidity = h,
+                MessageValue::Humidity(_, _) => (),
+                MessageValue::Pressure(Float::F16(p)) => self.pressure = p / 100.0,
+                //Instances that are not kept are ignored
+                MessageValue::EngineSpeed(i, Float::F16(rpm)) => 
+                    if let Some(e) = self.engines.get_mut(i as usize){ e.rpm = rpm },
+                MessageValue::CoolantTemperature(i, Float::F16(t)) => 
+                    if let Some(e) = self.engines.get_mut(i as usize){ e.coolant_temperature = to_celsius(t) },
+                MessageValue::FuelRate(i, Float::F16(rate)) => 
+                    if let Some(e) = self.engines.get_mut(i as usize){ e.fuel_rate = rate },
+                MessageValue::FluidLevel(FLUID_FUEL, i, Float::F16(level)) => 
+                    if let Some(l) = self.fuel_levels.get_mut(i as usize){ *l = level },
+                MessageValue::FluidLevel(_, _, _) => (),
+                MessageValue::BatteryVoltage(i, Float::F16(v)) => 
+                    if let Some(b) = self.batteries.get_mut(i as usize){ b.voltage = v },
+                MessageValue::BatteryCurrent(i, Float::F16(c)) => 
+                    if let Some(b) = self.batteries.get_mut(i as usize){ b.current = c },
+                MessageValue::BatteryTemperature(i, Float::F16(t)) => 
+                    if let Some(b) = self.batteries.get_mut(i as usize){ b.temperature = to_celsius(t) },
+                MessageValue::StateOfCharge(i, Float::F16(soc)) => 
+                    if let Some(b) = self.batteries.get_mut(i as usize){ b.state_of_charge = soc },
+                MessageValue::AisPosition(mmsi, Float::F32(lat), Float::F32(long), Float::F16(cog), Float::F16(sog)) => {
+                    self.vessels.position(mmsi, lat, long, to_degrees(cog), to_knots(sog), self.timestamp);
+                    self.check_collision(mmsi);
+                }
+                MessageValue::AisName(mmsi, name) => self.vessels.name(mmsi, name, self.timestamp),
+                MessageValue::AisCallsign(mmsi, callsign) => self.vessels.callsign(mmsi, callsign, self.timestamp),
+                //Other values, e.g., in an encoding that is not kept or of an unchanged destination, are ignored
+                _ => (),
             }
         }
+        if sog_updated{
+            self.integrate_sog(sog);
+        }
     }
-}
 
-/// Display state implementation for CSV document with separator `;`
-impl fmt::Display for State{
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+    /// Adds the distance at the `previous` SOG since the latest SOG to the SOG trip distance
+    fn integrate_sog(&mut self, previous: f32){
+        let t = self.timestamp.0 as f32 * 3600.0 + self.timestamp.1 as f32 * 60.0 + self.timestamp.2;
+        if let Some(last) = self.sog_time{
+            let mut dt = t - last;
+            //Passed midnight
+            if dt < -43_200.0{
+                dt += 86_400.0;
+            }
+            //Older than the latest SOG, e.g., from another input
+            if dt < 0.0{
+                return;
+            }
+            //Not available SOG is not integrated
+            if dt <= MAX_SOG_GAP && !previous.is_nan(){
+                self.sog_trip += previous * dt / 3600.0;
+            }
+        }
+        self.sog_time = Some(t);
+    }
+
+    /// Checks the collision alarm of the AIS target `mmsi` against the own position
+    fn check_collision(&mut self, mmsi: u32){
+        //Without an own position there is nothing to compare with, a not available COG or SOG
+        //is handled by the alarms
+        if (self.latitude == 0.0 && self.longitude == 0.0)
+            || !self.latitude.is_finite() || !self.longitude.is_finite(){
+            return;
+        }
+        if let (Some(alarms), Some(target)) = (&mut self.alarms, self.vessels.get(mmsi)){
+            alarms.check(self.latitude, self.longitude, self.cog, self.sog, target);
+        }
+    }
+
+    /// Returns the date of the written lines, `None` if no nmea date was read yet
+    fn date(&self) -> Option<NaiveDateTime>{
         if !self.sys_date{
             //Check if we can write out something, i.e., if we have read some nmea date
-            if self.got_nmea_date{
-                writeln!(f,
-                    "{:04}-{:02}-{:02} {:02}:{:02}:{:0>6.3};{:.1};{:.2};{};{};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2}",
-                    self.date_time.year(),self.date_time.month(),self.date_time.day(),self.timestamp.0, self.timestamp.1, self.timestamp.2,self.awa,self.aws,
-                    self.latitude,self.longitude,self.hdg,self.cog,self.sog,self.stw,
-                    self.rot,self.pitch,self.yaw,self.roll,self.rudder_angle)
-            }else{
-                Ok(())
+            if !self.got_nmea_date{
+                return None;
             }
+            Some(self.date_time)
         }else{
             let t = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap();
-            let date_time = NaiveDateTime::from_timestamp(t.as_secs() as i64,0);
-            writeln!(f,
-                "{:04}-{:02}-{:02} {:02}:{:02}:{:0>6.3};{:.1};{:.2};{};{};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2}",
-                date_time.year(),date_time.month(),date_time.day(),self.timestamp.0, self.timestamp.1, self.timestamp.2,self.awa,self.aws,
-                self.latitude,self.longitude,self.hdg,self.cog,self.sog,self.stw,
-                self.rot,self.pitch,self.yaw,self.roll,self.rudder_angle)
+            Some(DateTime::from_timestamp(t.as_secs() as i64,0).unwrap_or_default().naive_utc())
         }
     }
-}
\ No newline at end of file
+
+    /// Write the AIS position reports received since the last call as lines of a CSV document
+    /// with separator `;`, see [`crate::vessels::HEADLINE`]
+    pub fn write_reports<W: Write>(&mut self, writer: &mut W) -> io::Result<()>{
+        let reports = self.vessels.take_reports();
+        let date_time = match self.date(){
+            Some(d) => d,
+            None => return Ok(()),
+        };
+        for v in reports{
+            writeln!(writer, "{:04}-{:02}-{:02} {:02}:{:02}:{:0>6.3};{};{};{};{};{};{:.2};{:.2}",
+                date_time.year(),date_time.month(),date_time.day(),v.last_seen.0,v.last_seen.1,v.last_seen.2,
+                v.mmsi,Text(&v.name),Text(&v.callsign),v.latitude,v.longitude,Blank(v.cog),Blank(v.sog))?;
+        }
+        Ok(())
+    }
+
+    /// Write the collision alarms raised or cleared since the last call to stderr and, if given,
+    /// as lines of a CSV document with separator `;` to `log`, see [`crate::collision::HEADLINE`]
+    pub fn write_events<W: Write>(&mut self, mut log: Option<&mut W>) -> io::Result<()>{
+        let events = match &mut self.alarms{
+            Some(alarms) => alarms.take_events(),
+            None => return Ok(()),
+        };
+        //Alarms are never dropped, without a date only the time is written
+        let date = self.date().map(|d| format!("{:04}-{:02}-{:02} ", d.year(), d.month(), d.day()))
+                              .unwrap_or_default();
+        for e in events{
+            let time = format!("{}{:02}:{:02}:{:0>6.3}", date, e.timestamp.0, e.timestamp.1, e.timestamp.2);
+            let target = if e.name.is_empty() { e.mmsi.to_string() } else { format!("{} ({})", e.name, e.mmsi) };
+            if e.alarm{
+                eprintln!("{} collision alarm: {} CPA {:.2} NM in {:.1} min", time, target, e.cpa, e.tcpa);
+            }else{
+                eprintln!("{} collision alarm cleared: {} CPA {:.2} NM", time, target, e.cpa);
+            }
+            if let Some(w) = log.as_mut(){
+                writeln!(w, "{};{};{};{};{:.2};{:.1}", time, e.mmsi, Text(&e.name),
+                         if e.alarm { "alarm" } else { "clear" }, e.cpa, e.tcpa)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats a value that is not available, i.e., NaN, as empty field
+struct Blank(f32);
+
+impl fmt::Display for Blank{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        if self.0.is_nan(){
+            return Ok(());
+        }
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// Formats free text, e.g., a waypoint name, as field of a CSV document with separator `;`. Text
+/// containing the separator, commas, quotes or line breaks is quoted.
+struct Text<'a>(&'a str);
+
+impl fmt::Display for Text<'_>{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        if !self.0.contains(&[';', ',', '"', '\r', '\n'][..]){
+            return f.write_str(self.0);
+        }
+        write!(f, "\"{}\"", self.0.replace('"', "\"\""))
+    }
+}
+
+/// Display state implementation for CSV document with separator `;`
+impl fmt::Display for State{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let date_time = match self.date(){
+            Some(d) => d,
+            None => return Ok(()),
+        };
+        write!(f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:0>6.3};{:.1};{:.2};{:.1};{:.2};{:.1};{:.2};{:.1};{:.1};{};{};{};{};{};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.2};{:.1};{:.1};{:.1};{:.1};{:.2};{:.2};{:.2};{:.3};{:.1};{:.2};{:.2};{}",
+            date_time.year(),date_time.month(),date_time.day(),self.timestamp.0, self.timestamp.1, self.timestamp.2,Blank(self.awa),Blank(self.aws),
+            Blank(self.twa),Blank(self.tws),Blank(self.gwa),Blank(self.gws),Blank(self.twd),Blank(self.mwd),
+            Blank(self.latitude),Blank(self.longitude),self.fix,self.satellites,self.satellites_in_view,
+            Blank(self.hdop),Blank(self.pdop),Blank(self.vdop),Blank(self.geoidal_separation),Blank(self.hdg_true),Blank(self.hdg_magnetic),Blank(self.variation),Blank(self.cog),Blank(self.sog),Blank(self.stw),
+            Blank(self.rot),Blank(self.pitch),Blank(self.yaw),Blank(self.roll),Blank(self.rudder_angle),Blank(self.depth),
+            Blank(self.water_temperature),Blank(self.air_temperature),Blank(self.pressure),Blank(self.humidity),
+            Blank(self.log),Blank(self.trip_log),Blank(self.sog_trip),
+            Blank(self.xte),Blank(self.btw),Blank(self.dtw),Blank(self.vmg_wp),Text(&self.destination))?;
+        for e in &self.engines{
+            write!(f, ";{:.0};{:.1};{:.1}", Blank(e.rpm), Blank(e.coolant_temperature), Blank(e.fuel_rate))?;
+        }
+        for level in &self.fuel_levels{
+            write!(f, ";{:.1}", Blank(*level))?;
+        }
+        for b in &self.batteries{
+            write!(f, ";{:.2};{:.1};{:.1};{:.0}", Blank(b.voltage), Blank(b.current), Blank(b.temperature), Blank(b.state_of_charge))?;
+        }
+        if self.labels{
+            write!(f, ";{}", Text(&self.source))?;
+        }
+        writeln!(f)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    /// State with the date of June 16 2021
+    fn dated() -> State{
+        let mut state = State::new(false);
+        state.update(vec![MessageValue::Date(18794), MessageValue::Time(0.0), MessageValue::LocalOffset(0)]);
+        state
+    }
+
+    #[test]
+    fn text_quoting(){
+        assert_eq!(Text("MARK 1").to_string(), "MARK 1");
+        assert_eq!(Text("MARK;1").to_string(), "\"MARK;1\"");
+        assert_eq!(Text("MARK, 1").to_string(), "\"MARK, 1\"");
+        assert_eq!(Text("\"MARK\" 1").to_string(), "\"\"\"MARK\"\" 1\"");
+        assert_eq!(Text("MARK\r\n1").to_string(), "\"MARK\r\n1\"");
+    }
+
+    #[test]
+    fn destination_quoting(){
+        let mut state = dated();
+        state.update(vec![MessageValue::DestinationWaypoint(1),
+                          MessageValue::Waypoint(1, "LEE \"MARK\", 1".to_string())]);
+        assert!(state.to_string().contains(";\"LEE \"\"MARK\"\", 1\";"));
+    }
+
+    #[test]
+    fn reports_quoting(){
+        let mut state = dated();
+        state.vessels.track = true;
+        state.update(vec![MessageValue::AisName(211000001, "WIND, \"SEA\"".to_string()),
+                          MessageValue::AisCallsign(211000001, "DA;1".to_string()),
+                          MessageValue::AisPosition(211000001, Float::F32(54.5), Float::F32(10.25),
+                                                    Float::F16(0.0), Float::F16(1.0))]);
+        let mut reports = Vec::new();
+        state.write_reports(&mut reports).unwrap();
+        assert_eq!(String::from_utf8(reports).unwrap(),
+                   "2021-06-16 00:00:00.000;211000001;\"WIND, \"\"SEA\"\"\";\"DA;1\";54.5;10.25;0.00;1.94\n");
+    }
+
+    #[test]
+    fn events_quoting(){
+        let mut state = dated();
+        state.alarms = Some(Alarms::new(0.5, 15.0));
+        (state.latitude, state.longitude, state.cog, state.sog) = (50.0, 0.0, 0.0, 10.0);
+        //Target 1 NM north on the reciprocal course at 10 kn
+        state.update(vec![MessageValue::AisName(211000001, "WIND, \"SEA\"".to_string()),
+                          MessageValue::AisPosition(211000001, Float::F32(50.0 + 1.0 / 60.0), Float::F32(0.0),
+                                                    Float::F16(std::f32::consts::PI), Float::F16(10.0 / 1.943_844))]);
+        let mut log = Vec::new();
+        state.write_events(Some(&mut log)).unwrap();
+        assert_eq!(String::from_utf8(log).unwrap(),
+                   "2021-06-16 00:00:00.000;211000001;\"WIND, \"\"SEA\"\"\";alarm;0.00;3.0\n");
+    }
+
+    #[test]
+    fn source_quoting(){
+        let mut state = dated();
+        state.labels = true;
+        state.update_from("file:log;1.txt", vec![MessageValue::Timestamp((12, 0, 0.0))]);
+        assert!(state.to_string().ends_with(";\"file:log;1.txt\"\n"));
+    }
+}
@@ -0,0 +1,83 @@
+//! Implementation of a line based TCP client, e.g., for the RAW server of a Yacht Devices gateway.
+//! Never closes, i.e., reconnects whenever the connection is lost.
+use std::io::{self, BufRead, BufReader};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+/// Initial delay before reconnecting after a failed connection attempt or a dropped connection
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+/// Maximal delay between connection attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// The connection is considered lost if nothing was received for this long
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Iterates over the lines received from a TCP server.
+/// 
+/// If the connection drops, it is re-established with an exponential backoff between
+/// attempts. Only complete lines are returned, i.e., a line that was interrupted by a 
+/// dropped connection is discarded. The iterator never ends.
+pub struct TcpLines{
+    addr: String,
+    reader: Option<BufReader<TcpStream>>,
+    backoff: Duration
+}
+
+impl TcpLines{
+    /// Returns a new client for `addr`. Connecting is deferred to the first read.
+    pub fn new(addr: String) -> Self{
+        TcpLines{addr, reader: None, backoff: MIN_BACKOFF}
+    }
+
+    /// Connects to `addr`, blocks until a connection is established.
+    fn connect(&mut self) -> BufReader<TcpStream>{
+        loop{
+            match TcpStream::connect(&self.addr)
+                    .and_then(|s| s.set_read_timeout(Some(READ_TIMEOUT)).map(|_| s)){
+                Ok(stream) => {
+                    eprintln!("connected to {}", self.addr);
+                    return BufReader::new(stream);
+                }
+                Err(e) => {
+                    eprintln!("could not connect to {}: {}, retrying in {}s", 
+                              self.addr, e, self.backoff.as_secs());
+                    thread::sleep(self.backoff);
+                    self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for TcpLines{
+    type Item = io::Result<String>;
+
+    /// Reads the next complete line without line ending. Never returns `None`.
+    fn next(&mut self) -> Option<Self::Item>{
+        let mut line = String::new();
+        loop{
+            let mut reader = match self.reader.take(){
+                Some(reader) => reader,
+                None => self.connect()
+            };
+            line.clear();
+            match reader.read_line(&mut line){
+                Ok(_) if line.ends_with('\n') => {
+                    self.reader = Some(reader);
+                    self.backoff = MIN_BACKOFF;
+                    let l = line.trim_end_matches(&['\r','\n'][..]).len();
+                    line.truncate(l);
+                    return Some(Ok(line));
+                }
+                Ok(_) => eprintln!("connection to {} closed, reconnecting in {}s",
+                                   self.addr, self.backoff.as_secs()),
+                Err(e) => eprintln!("connection to {} lost: {}, reconnecting in {}s",
+                                    self.addr, e, self.backoff.as_secs()),
+            }
+            //Servers that accept and immediately drop connections, e.g., if the client limit
+            //of the gateway is reached, are retried with the same backoff as failed attempts
+            thread::sleep(self.backoff);
+            self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Table of the vessels nearby, i.e., the AIS targets.
+use crate::nmea::types::Timestamp;
+
+use std::collections::BTreeMap;
+
+/// Headline of the CSV document of the AIS position reports
+pub const HEADLINE: &str = "time;mmsi;name;callsign;latitude;longitude;cog;sog";
+
+/// Latest values of an AIS target
+#[derive(Clone, Default)]
+pub struct Vessel{
+    /// Maritime Mobile Service Identity
+    pub mmsi : u32,
+    /// Name of the vessel, empty until the static data was received
+    pub name : String,
+    /// Call sign of the vessel, empty until the static data was received
+    pub callsign : String,
+    /// Latitude
+    pub latitude : f32,
+    /// Longitude
+    pub longitude : f32,
+    /// Course over ground in degrees
+    pub cog : f32,
+    /// Speed over ground in knots
+    pub sog : f32,
+    /// Timestamp of the latest message of the vessel
+    pub last_seen : Timestamp,
+}
+
+/// Keeps the latest values of the AIS targets by MMSI.
+#[derive(Default)]
+pub struct Vessels{
+    table: BTreeMap<u32, Vessel>,
+    /// Position reports since the last call of [`Vessels::take_reports`]
+    reports: Vec<Vessel>,
+    /// Flag if the position reports are kept, i.e., if the tracks are written.
+    pub track: bool,
+}
+
+impl Vessels{
+    /// Create new empty table
+    pub fn new() -> Vessels{
+        Vessels::default()
+    }
+
+    /// Returns the entry of `mmsi` and updates the time it was last seen
+    fn seen(&mut self, mmsi: u32, timestamp: Timestamp) -> &mut Vessel{
+        let vessel = self.table.entry(mmsi).or_insert_with(|| Vessel{mmsi, ..Default::default()});
+        vessel.last_seen = timestamp;
+        vessel
+    }
+
+    /// Update the table with a position report
+    pub fn position(&mut self, mmsi: u32, latitude: f32, longitude: f32, cog: f32, sog: f32, timestamp: Timestamp){
+        let vessel = self.seen(mmsi, timestamp);
+        vessel.latitude = latitude;
+        vessel.longitude = longitude;
+        vessel.cog = cog;
+        vessel.sog = sog;
+        if self.track{
+            let report = self.table[&mmsi].clone();
+            self.reports.push(report);
+        }
+    }
+
+    /// Update the name of a vessel
+    pub fn name(&mut self, mmsi: u32, name: String, timestamp: Timestamp){
+        self.seen(mmsi, timestamp).name = name;
+    }
+
+    /// Update the call sign of a vessel
+    pub fn callsign(&mut self, mmsi: u32, callsign: String, timestamp: Timestamp){
+        self.seen(mmsi, timestamp).callsign = callsign;
+    }
+
+    /// Returns the vessel with `mmsi`
+    pub fn get(&self, mmsi: u32) -> Option<&Vessel>{
+        self.table.get(&mmsi)
+    }
+
+    /// Returns the position reports received since the last call
+    pub fn take_reports(&mut self) -> Vec<Vessel>{
+        std::mem::take(&mut self.reports)
+    }
+}